@@ -0,0 +1,390 @@
+use anyhow::Result;
+use log::{debug, info, warn};
+
+use crate::errors::Error;
+use crate::eventspec::{Motion, ScrollDirection};
+
+/// An input injection backend driven by `scheduler::InputEventQueue`.
+///
+/// Backends only need to know how to press and release a single key or
+/// button, move the pointer, scroll, and tell whether the user has paused input; all
+/// timing decisions, including how long to hold keys down and how to pace a
+/// pointer path, are made by the scheduler.
+pub trait InputBackend {
+    /// Press the named key, leaving it held down.
+    fn key_down(&mut self, key: &str) -> Result<()>;
+
+    /// Release the named key.
+    fn key_up(&mut self, key: &str) -> Result<()>;
+
+    /// Press the numbered mouse button, leaving it held down.
+    fn button_down(&mut self, button: u8) -> Result<()>;
+
+    /// Release the numbered mouse button.
+    fn button_up(&mut self, button: u8) -> Result<()>;
+
+    /// Press and immediately release the named key.
+    fn emit_key(&mut self, key: &str) -> Result<()> {
+        self.key_down(key)?;
+        self.key_up(key)
+    }
+
+    /// Press and immediately release the numbered mouse button.
+    fn emit_button(&mut self, button: u8) -> Result<()> {
+        self.button_down(button)?;
+        self.button_up(button)
+    }
+
+    /// Move the pointer by `dx`, `dy` pixels.
+    fn move_by(&mut self, dx: i32, dy: i32) -> Result<()>;
+
+    /// Move the pointer to `x`, `y` on the screen.
+    fn move_to(&mut self, x: i32, y: i32) -> Result<()>;
+
+    /// Move the pointer as described by `motion`.
+    fn move_pointer(&mut self, motion: Motion) -> Result<()> {
+        match motion {
+            Motion::By(dx, dy) => self.move_by(dx, dy),
+            Motion::To(x, y) => self.move_to(x, y),
+        }
+    }
+
+    /// Turn the scroll wheel `amount` [`WHEEL_DETENT`]ths of a detent in
+    /// `direction`. Backends that can only scroll whole detents should carry
+    /// the remainder over to the next call.
+    ///
+    /// [`WHEEL_DETENT`]: crate::eventspec::WHEEL_DETENT
+    fn scroll(&mut self, direction: ScrollDirection, amount: u32) -> Result<()>;
+
+    /// Where the pointer is on the screen, if the backend can tell.
+    fn pointer_position(&mut self) -> Option<(i32, i32)> {
+        None
+    }
+
+    /// Check that [`InputBackend::move_to`] can place the pointer, before
+    /// the run starts.
+    fn check_move_to(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check that [`InputBackend::scroll`] can turn the wheel, before the
+    /// run starts. Backends that need a device to scroll with set it up here.
+    fn check_scroll(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether input is currently paused (numlock off).
+    fn paused(&self) -> bool;
+
+    /// Check that the named key can be sent, before the run starts.
+    fn check_key(&mut self, _key: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check that the numbered mouse button can be sent, before the run
+    /// starts.
+    fn check_button(&mut self, _button: u8) -> Result<()> {
+        Ok(())
+    }
+
+    /// Enter a character that isn't on any key of a US layout, e.g. `é` or
+    /// `😀`. Backends can't do this unless they say otherwise.
+    fn type_unicode(&mut self, c: char) -> Result<()> {
+        Err(Error::UnicodeUnavailable(c).into())
+    }
+
+    /// Check that [`InputBackend::type_unicode`] can enter `c`, before the
+    /// run starts.
+    fn check_unicode(&mut self, c: char) -> Result<()> {
+        Err(Error::UnicodeUnavailable(c).into())
+    }
+}
+
+/// How a backend with no way of its own to enter characters that aren't on
+/// a key, like `é` or `😀`, types them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnicodeInput {
+    /// It doesn't, and texts with such characters are rejected.
+    #[default]
+    None,
+    /// Press ctrl+shift+u, type the character's code point in hex, and
+    /// press space, which IBus and GTK input methods understand.
+    CtrlShiftU,
+}
+
+impl UnicodeInput {
+    pub const ALL: &'static [UnicodeInput] = &[UnicodeInput::None, UnicodeInput::CtrlShiftU];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            UnicodeInput::None => "none",
+            UnicodeInput::CtrlShiftU => "ctrl-shift-u",
+        }
+    }
+
+    /// The keys to press, one after another, to enter `c`, or `None` if it
+    /// can't be entered.
+    pub fn keys(&self, c: char) -> Option<Vec<String>> {
+        match *self {
+            UnicodeInput::None => None,
+            UnicodeInput::CtrlShiftU => Some(
+                std::iter::once("ctrl+shift+u".to_owned())
+                    .chain(format!("{:x}", c as u32).chars().map(String::from))
+                    .chain(std::iter::once("space".to_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for UnicodeInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for UnicodeInput {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|method| method.name() == s)
+            .ok_or_else(|| Error::UnicodeInputMethod(s.to_owned()))
+    }
+}
+
+/// The size of the screen in pixels, which backends that can't ask a
+/// display server need in order to move the pointer to a screen position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScreenSize {
+    /// The size of the only connected display, as the kernel's DRM drivers
+    /// report it.
+    pub fn detect() -> Option<Self> {
+        let connectors = std::fs::read_dir("/sys/class/drm")
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let status = std::fs::read_to_string(path.join("status")).ok()?;
+                let modes = std::fs::read_to_string(path.join("modes")).ok()?;
+                Some((status, modes))
+            });
+        Self::from_connectors(connectors)
+    }
+
+    /// The preferred mode of the only connected connector, given the
+    /// contents of each connector's `status` and `modes` files. How several
+    /// displays are arranged isn't known, so there's no size for them.
+    fn from_connectors<I: IntoIterator<Item = (String, String)>>(connectors: I) -> Option<Self> {
+        let mut connected = connectors
+            .into_iter()
+            .filter(|(status, _)| status.trim() == "connected")
+            .collect::<Vec<_>>();
+        if connected.len() > 1 {
+            warn!(
+                "{} displays are connected, and how they're arranged isn't known. Give the size of the whole screen with --screen-size to move the pointer to screen positions.",
+                connected.len()
+            );
+            return None;
+        }
+        let (_, modes) = connected.pop()?;
+        modes.lines().next()?.parse().ok()
+    }
+}
+
+impl std::fmt::Display for ScreenSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+impl std::str::FromStr for ScreenSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        // Modes can have a suffix, as in 1920x1080i
+        let dimension = |d: &str| {
+            d.trim_end_matches(|c: char| c.is_ascii_alphabetic())
+                .parse::<u32>()
+                .ok()
+                .filter(|d| *d > 0 && *d <= i32::MAX as u32)
+        };
+        s.split_once('x')
+            .and_then(|(width, height)| {
+                Some(ScreenSize {
+                    width: dimension(width)?,
+                    height: dimension(height)?,
+                })
+            })
+            .ok_or_else(|| Error::ScreenSize(s.to_owned()))
+    }
+}
+
+/// The input backends compiled into this build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    DryRun,
+    #[cfg(feature = "uinput")]
+    UInput,
+    #[cfg(feature = "x11")]
+    X11,
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl BackendKind {
+    /// Name accepted by `--backend` to force automatic selection.
+    pub const AUTO: &'static str = "auto";
+
+    pub const ALL: &'static [BackendKind] = &[
+        BackendKind::DryRun,
+        #[cfg(feature = "uinput")]
+        BackendKind::UInput,
+        #[cfg(feature = "x11")]
+        BackendKind::X11,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            BackendKind::DryRun => "dry-run",
+            #[cfg(feature = "uinput")]
+            BackendKind::UInput => "uinput",
+            #[cfg(feature = "x11")]
+            BackendKind::X11 => "x11",
+        }
+    }
+
+    /// Resolve a `--backend` value, probing the environment for `auto`.
+    ///
+    /// `auto` never selects the dry-run backend; it has to be asked for.
+    pub fn select(name: &str, display_name: Option<&str>) -> Result<Self> {
+        if name == Self::AUTO {
+            return Self::probe(display_name);
+        }
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| {
+                let names = Self::ALL
+                    .iter()
+                    .map(|kind| kind.name().to_owned())
+                    .chain(std::iter::once(Self::AUTO.to_owned()))
+                    .collect();
+                Error::UnknownBackend(name.to_owned(), names).into()
+            })
+    }
+
+    fn probe(display_name: Option<&str>) -> Result<Self> {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+        let display = display_name
+            .map(|name| name.to_owned())
+            .or_else(|| std::env::var("DISPLAY").ok())
+            .filter(|name| !name.is_empty());
+        let uinput_writable = std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/uinput")
+            .is_ok();
+        debug!(
+            "Backend probe: wayland session: {}, X11 display: {:?}, /dev/uinput writable: {}",
+            wayland, display, uinput_writable
+        );
+
+        // XTest only reaches X clients, so under wayland prefer uinput, which
+        // works with every compositor. Otherwise prefer x11, which doesn't
+        // need any special device permissions.
+        let candidates: Vec<Option<(BackendKind, String)>> = vec![
+            #[cfg(feature = "x11")]
+            display.as_ref().filter(|_| !wayland).map(|display| {
+                (
+                    BackendKind::X11,
+                    format!("X11 display {} is available", display),
+                )
+            }),
+            #[cfg(feature = "uinput")]
+            Some((BackendKind::UInput, "/dev/uinput is writable".to_owned()))
+                .filter(|_| uinput_writable),
+            #[cfg(feature = "x11")]
+            display.as_ref().filter(|_| wayland).map(|display| {
+                (
+                    BackendKind::X11,
+                    format!(
+                        "X11 display {} is available, but only XWayland clients will see input",
+                        display
+                    ),
+                )
+            }),
+        ];
+
+        match candidates.into_iter().flatten().next() {
+            Some((kind, reason)) => {
+                info!("Selected {} backend: {}", kind, reason);
+                Ok(kind)
+            }
+            None => Err(Error::NoBackendAvailable.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ctrl_shift_u_types_the_code_point() {
+        assert_eq!(
+            UnicodeInput::CtrlShiftU.keys('é').unwrap(),
+            vec!["ctrl+shift+u", "e", "9", "space"]
+        );
+        assert_eq!(UnicodeInput::CtrlShiftU.keys('😀').unwrap().len(), 7);
+        assert_eq!(UnicodeInput::None.keys('é'), None);
+    }
+
+    #[test]
+    fn unknown_backends_are_named() {
+        assert_eq!(
+            BackendKind::select("dry-run", None).unwrap(),
+            BackendKind::DryRun
+        );
+        let message = BackendKind::select("wayland", None)
+            .unwrap_err()
+            .to_string();
+        assert!(message.starts_with("Backend wayland is not available (expected dry-run, "));
+        assert!(message.ends_with("auto)."));
+    }
+
+    #[test]
+    fn screen_sizes_parse_and_detect() {
+        let size = |width, height| Some(ScreenSize { width, height });
+        assert_eq!("1920x1080".parse().ok(), size(1920, 1080));
+        assert!("1920".parse::<ScreenSize>().is_err());
+        assert!("0x1080".parse::<ScreenSize>().is_err());
+        let connector = |status: &str, modes: &str| (status.to_owned(), modes.to_owned());
+        assert_eq!(
+            ScreenSize::from_connectors(vec![
+                connector("disconnected\n", ""),
+                connector("connected\n", "2560x1440\n1920x1080\n"),
+            ]),
+            size(2560, 1440)
+        );
+        assert_eq!(
+            ScreenSize::from_connectors(vec![
+                connector("connected\n", "2560x1440\n"),
+                connector("connected\n", "1920x1080\n"),
+            ]),
+            None
+        );
+        assert_eq!(ScreenSize::from_connectors(Vec::new()), None);
+    }
+}
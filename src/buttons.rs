@@ -0,0 +1,99 @@
+//! Mouse button names and numbers understood by every backend.
+//!
+//! Buttons are numbered from 1, as X11 numbers them: 1 to 3 are the left,
+//! middle and right buttons, 4 to 7 turn the scroll wheel up, down, left
+//! and right, and the kernel's extra buttons follow from 8 in the order the
+//! X.org input drivers number them. Buttons with a kernel code can also be
+//! given by name.
+
+use crate::eventspec::ScrollDirection;
+
+/// Button names, with their X11 numbers and kernel codes.
+pub const BUTTONS: &[(&str, u8, u16)] = &[
+    ("left", 1, 0x110),
+    ("middle", 2, 0x112),
+    ("right", 3, 0x111),
+    // Most mice send side and extra from the thumb buttons that browsers
+    // use for back and forward
+    ("side", 8, 0x113),
+    ("extra", 9, 0x114),
+    ("forward", 10, 0x115),
+    ("back", 11, 0x116),
+    ("task", 12, 0x117),
+];
+
+/// The button numbered or named (case-insensitively) by `name`.
+pub fn parse(name: &str) -> Option<u8> {
+    match name.parse::<u8>() {
+        Ok(0) => None,
+        Ok(button) => Some(button),
+        Err(_) => BUTTONS
+            .iter()
+            .find(|(button_name, _, _)| button_name.eq_ignore_ascii_case(name))
+            .map(|(_, button, _)| *button),
+    }
+}
+
+/// The name of a button, if it has one.
+pub fn name(button: u8) -> Option<&'static str> {
+    BUTTONS
+        .iter()
+        .find(|(_, number, _)| *number == button)
+        .map(|(name, _, _)| *name)
+}
+
+/// The kernel code for a button, if it has one.
+pub fn evdev_code(button: u8) -> Option<u16> {
+    BUTTONS
+        .iter()
+        .find(|(_, number, _)| *number == button)
+        .map(|(_, _, code)| *code)
+}
+
+/// Which way a wheel button scrolls, if it's one of buttons 4 to 7.
+pub fn wheel_direction(button: u8) -> Option<ScrollDirection> {
+    match button {
+        4 => Some(ScrollDirection::Up),
+        5 => Some(ScrollDirection::Down),
+        6 => Some(ScrollDirection::Left),
+        7 => Some(ScrollDirection::Right),
+        _ => None,
+    }
+}
+
+/// A button's number, followed by its name or scroll direction if it has
+/// one, e.g. `8 (side)`, for error messages.
+pub fn label(button: u8) -> String {
+    match (name(button), wheel_direction(button)) {
+        (Some(name), _) => format!("{} ({})", button, name),
+        (None, Some(direction)) => format!("{} (scroll {})", button, direction),
+        (None, None) => button.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buttons_parse_by_number_or_name() {
+        assert_eq!(parse("1"), Some(1));
+        assert_eq!(parse("left"), Some(1));
+        assert_eq!(parse("Right"), Some(3));
+        assert_eq!(parse("side"), Some(8));
+        assert_eq!(parse("task"), Some(12));
+        assert_eq!(parse("20"), Some(20));
+        assert_eq!(parse("0"), None);
+        assert_eq!(parse("256"), None);
+        assert_eq!(parse("thumb"), None);
+    }
+
+    #[test]
+    fn buttons_are_labelled() {
+        assert_eq!(label(2), "2 (middle)");
+        assert_eq!(label(5), "5 (scroll down)");
+        assert_eq!(label(13), "13");
+        assert_eq!(evdev_code(9), Some(0x114));
+        assert_eq!(evdev_code(4), None);
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::Result;
+
+use crate::errors::Error;
+use crate::keys;
+
+/// A modifier key that can be held down around another key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    LeftShift,
+    RightShift,
+    LeftControl,
+    RightControl,
+    LeftAlt,
+    RightAlt,
+    LeftSuper,
+    RightSuper,
+    /// The third-level shift key, which most layouts put on right alt.
+    AltGr,
+}
+
+impl Modifier {
+    /// Names accepted for each modifier, matched case-insensitively. A bare
+    /// name means the left-hand key, and Meta is another name for Super, as
+    /// it is in the kernel's key names.
+    const NAMES: &'static [(&'static str, Modifier)] = &[
+        ("shift", Modifier::LeftShift),
+        ("lshift", Modifier::LeftShift),
+        ("shift_l", Modifier::LeftShift),
+        ("rshift", Modifier::RightShift),
+        ("shift_r", Modifier::RightShift),
+        ("ctrl", Modifier::LeftControl),
+        ("control", Modifier::LeftControl),
+        ("lctrl", Modifier::LeftControl),
+        ("control_l", Modifier::LeftControl),
+        ("rctrl", Modifier::RightControl),
+        ("control_r", Modifier::RightControl),
+        ("alt", Modifier::LeftAlt),
+        ("lalt", Modifier::LeftAlt),
+        ("alt_l", Modifier::LeftAlt),
+        ("ralt", Modifier::RightAlt),
+        ("alt_r", Modifier::RightAlt),
+        ("super", Modifier::LeftSuper),
+        ("lsuper", Modifier::LeftSuper),
+        ("super_l", Modifier::LeftSuper),
+        ("meta", Modifier::LeftSuper),
+        ("lmeta", Modifier::LeftSuper),
+        ("meta_l", Modifier::LeftSuper),
+        ("win", Modifier::LeftSuper),
+        ("rsuper", Modifier::RightSuper),
+        ("super_r", Modifier::RightSuper),
+        ("rmeta", Modifier::RightSuper),
+        ("meta_r", Modifier::RightSuper),
+        ("altgr", Modifier::AltGr),
+        ("iso_level3_shift", Modifier::AltGr),
+    ];
+
+    /// Look up a modifier by name, e.g. `ctrl`, `rshift` or `altgr`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        Self::NAMES
+            .iter()
+            .find(|(modifier_name, _)| *modifier_name == name)
+            .map(|(_, modifier)| *modifier)
+    }
+
+    /// The X keysym name for the key.
+    pub fn keysym_name(&self) -> &'static str {
+        match *self {
+            Modifier::LeftShift => "Shift_L",
+            Modifier::RightShift => "Shift_R",
+            Modifier::LeftControl => "Control_L",
+            Modifier::RightControl => "Control_R",
+            Modifier::LeftAlt => "Alt_L",
+            Modifier::RightAlt => "Alt_R",
+            Modifier::LeftSuper => "Super_L",
+            Modifier::RightSuper => "Super_R",
+            Modifier::AltGr => "ISO_Level3_Shift",
+        }
+    }
+
+    /// Whether this is one of the shift keys.
+    pub fn is_shift(&self) -> bool {
+        matches!(*self, Modifier::LeftShift | Modifier::RightShift)
+    }
+
+    /// The modifiers to hold down to reach a shift level of a key, from 0
+    /// for the key on its own to 3 for AltGr+Shift.
+    pub fn for_level(level: u8) -> Vec<Modifier> {
+        match level {
+            0 => vec![],
+            1 => vec![Modifier::LeftShift],
+            2 => vec![Modifier::AltGr],
+            _ => vec![Modifier::AltGr, Modifier::LeftShift],
+        }
+    }
+}
+
+/// The first of the four shift levels of a key that has `keysym` on it,
+/// given the keysym on each level as the keymap has them.
+pub fn key_level<T: PartialEq>(keysym: T, level_keysym: impl Fn(u8) -> T) -> Option<u8> {
+    (0..4).find(|level| level_keysym(*level) == keysym)
+}
+
+/// The modifiers in `held`, followed by those in `needed` that aren't held
+/// already. Either shift key counts as holding shift.
+pub fn merge_modifiers(held: &[Modifier], needed: &[Modifier]) -> Vec<Modifier> {
+    let mut modifiers = held.to_vec();
+    for modifier in needed {
+        let held = modifiers.contains(modifier)
+            || (modifier.is_shift() && modifiers.iter().any(Modifier::is_shift));
+        if !held {
+            modifiers.push(*modifier);
+        }
+    }
+    modifiers
+}
+
+impl std::fmt::Display for Modifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.keysym_name())
+    }
+}
+
+/// A key pressed with zero or more modifiers held down, e.g.
+/// `ctrl+alt+Delete`.
+///
+/// Modifiers are pressed in the order they're given, before the key, and
+/// released in the reverse order after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chord {
+    pub modifiers: Vec<Modifier>,
+    pub key: String,
+}
+
+impl Chord {
+    /// Parse `MODIFIER+...+KEY`. The key itself may be `+`, as in `ctrl++`.
+    pub fn parse(chord_str: &str) -> Result<Self> {
+        let (modifiers_str, key) = if chord_str == "+" {
+            ("", "+")
+        } else if let Some(modifiers_str) = chord_str.strip_suffix("++") {
+            (modifiers_str, "+")
+        } else {
+            chord_str.rsplit_once('+').unwrap_or(("", chord_str))
+        };
+        if key.is_empty() {
+            return Err(Error::KeyboardEventSpec(chord_str.to_owned()).into());
+        }
+
+        let mut modifiers: Vec<Modifier> = Vec::new();
+        if !modifiers_str.is_empty() {
+            for name in modifiers_str.split('+') {
+                let modifier =
+                    Modifier::from_name(name).ok_or_else(|| Error::KeyModifier(name.to_owned()))?;
+                if !modifiers.contains(&modifier) {
+                    modifiers.push(modifier);
+                }
+            }
+        }
+        Ok(Chord {
+            modifiers,
+            key: key.to_owned(),
+        })
+    }
+
+    /// The modifiers to hold down around the key, adding shift for keys like
+    /// `T` or `!` that need it on a US layout, unless a shift key is already
+    /// part of the chord.
+    pub fn held_modifiers(&self) -> Vec<Modifier> {
+        let mut modifiers = self.modifiers.clone();
+        let needs_shift = keys::lookup(&self.key).is_some_and(|key| key.shift);
+        if needs_shift && !modifiers.iter().any(Modifier::is_shift) {
+            modifiers.push(Modifier::LeftShift);
+        }
+        modifiers
+    }
+
+    /// The modifiers to hold down around the key when it's on `level` of a
+    /// keymap's key, adding whatever reaches that level unless the chord
+    /// already holds it.
+    pub fn modifiers_for_level(&self, level: u8) -> Vec<Modifier> {
+        merge_modifiers(&self.modifiers, &Modifier::for_level(level))
+    }
+}
+
+impl std::fmt::Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(chord_str: &str) -> (Vec<Modifier>, String) {
+        let chord = Chord::parse(chord_str).unwrap();
+        (chord.modifiers, chord.key)
+    }
+
+    #[test]
+    fn chords_parse_modifiers_in_order() {
+        assert_eq!(chord("a"), (vec![], "a".to_owned()));
+        assert_eq!(
+            chord("ctrl+alt+Delete"),
+            (
+                vec![Modifier::LeftControl, Modifier::LeftAlt],
+                "Delete".to_owned()
+            )
+        );
+        assert_eq!(
+            chord("RShift+Super_L+AltGr+e"),
+            (
+                vec![Modifier::RightShift, Modifier::LeftSuper, Modifier::AltGr],
+                "e".to_owned()
+            )
+        );
+        assert_eq!(chord("meta+l"), chord("super+l"));
+        assert_eq!(chord("ctrl+ctrl+c"), chord("ctrl+c"));
+    }
+
+    #[test]
+    fn plus_can_be_the_key() {
+        assert_eq!(chord("+"), (vec![], "+".to_owned()));
+        assert_eq!(
+            chord("ctrl++"),
+            (vec![Modifier::LeftControl], "+".to_owned())
+        );
+    }
+
+    #[test]
+    fn shifted_keys_hold_shift() {
+        let held = |chord_str| Chord::parse(chord_str).unwrap().held_modifiers();
+        assert_eq!(held("ctrl+t"), vec![Modifier::LeftControl]);
+        assert_eq!(
+            held("ctrl+T"),
+            vec![Modifier::LeftControl, Modifier::LeftShift]
+        );
+        assert_eq!(held("rshift+T"), vec![Modifier::RightShift]);
+        assert_eq!(held("!"), vec![Modifier::LeftShift]);
+    }
+
+    #[test]
+    fn levels_come_from_the_keymap() {
+        // The q and 2 keys on a German keymap: @ is on AltGr+q, and " is on
+        // shift+2 rather than shift+' as it is on a US keymap.
+        let q = |level| ["q", "Q", "at", "Greek_OMEGA"][usize::from(level)];
+        let two = |level| ["2", "quotedbl", "twosuperior", "oneeighth"][usize::from(level)];
+        assert_eq!(key_level("at", q), Some(2));
+        assert_eq!(key_level("quotedbl", two), Some(1));
+        assert_eq!(key_level("2", two), Some(0));
+        assert_eq!(key_level("apostrophe", two), None);
+
+        let held = |chord_str, level| Chord::parse(chord_str).unwrap().modifiers_for_level(level);
+        assert_eq!(held("@", 2), vec![Modifier::AltGr]);
+        assert_eq!(
+            held("ctrl+quotedbl", 1),
+            vec![Modifier::LeftControl, Modifier::LeftShift]
+        );
+        assert_eq!(held("rshift+quotedbl", 1), vec![Modifier::RightShift]);
+        assert_eq!(held("altgr+@", 2), vec![Modifier::AltGr]);
+        assert_eq!(held("q", 0), vec![]);
+    }
+
+    #[test]
+    fn bad_chords_are_rejected() {
+        assert!(Chord::parse("hyper+a").is_err());
+        assert!(Chord::parse("ctrl+").is_err());
+        assert!(Chord::parse("").is_err());
+        assert!(Chord::parse("ctrl++a").is_err());
+    }
+}
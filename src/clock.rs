@@ -0,0 +1,94 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// A source of time for the scheduler.
+pub trait Clock {
+    /// The current time.
+    fn now(&self) -> Instant;
+
+    /// Block until `duration` has passed.
+    fn sleep(&self, duration: Duration);
+}
+
+/// The system clock, counting time spent suspended like `CLOCK_BOOTTIME`,
+/// so the scheduler sees the intervals that passed while the machine was
+/// suspended as missed. Without `CLOCK_BOOTTIME` it falls back to
+/// [`Instant::now`], which doesn't.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealClock;
+
+/// Time since boot, including time spent suspended, or `None` if the kernel
+/// can't tell.
+fn boottime() -> Option<Duration> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let status = unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut ts) };
+    (status == 0).then(|| Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant {
+        // Instants stop while suspended, so count from a fixed one by the
+        // boot time that has passed since
+        static EPOCH: OnceLock<(Instant, Option<Duration>)> = OnceLock::new();
+        let (instant, boot) = *EPOCH.get_or_init(|| (Instant::now(), boottime()));
+        match (boot, boottime()) {
+            (Some(boot), Some(now)) => instant + now.saturating_sub(boot),
+            _ => Instant::now(),
+        }
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// A clock that only moves when slept on, and then advances instantly.
+///
+/// Clones share the same time, so a test can keep a handle to the clock it
+/// gave the scheduler and inspect it as the schedule runs.
+#[derive(Debug, Clone)]
+pub struct VirtualClock {
+    start: Instant,
+    elapsed: Rc<Cell<Duration>>,
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        VirtualClock {
+            start: Instant::now(),
+            elapsed: Rc::new(Cell::new(Duration::from_millis(0))),
+        }
+    }
+}
+
+impl VirtualClock {
+    /// Create a clock with no time elapsed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Virtual time passed since the clock was created.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+
+    /// Move the clock forward by `duration` without sleeping.
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
@@ -0,0 +1,254 @@
+//! Configuration files holding named profiles, each a set of events and the
+//! settings to run them with, as they'd be given on the command line.
+//!
+//! ```toml
+//! [profiles.farming]
+//! description = "Harvest and replant"
+//! keys = ["e:500ms", "ctrl+s:5m"]
+//! mouse = ["left@640,480:2s~500ms"]
+//! sequences = ["ctrl+a, wait 50ms, ctrl+c:1m"]
+//! delay = "5s"
+//! pause-source = "numlock"
+//! backend = "uinput"
+//! max-runtime = "2h"
+//! ```
+//!
+//! Files ending in `.yaml` or `.yml` are read as YAML, with the same keys,
+//! and anything else as TOML.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::errors::Error;
+use crate::eventspec::{parse_duration, EventSpec};
+use crate::script;
+
+/// A duration setting, given either as a number of milliseconds or as a
+/// duration like `"1.5s"`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum DurationSetting {
+    Millis(u64),
+    Text(String),
+}
+
+impl DurationSetting {
+    /// The duration the setting stands for.
+    pub fn duration(&self) -> Result<std::time::Duration> {
+        match *self {
+            DurationSetting::Millis(millis) => Ok(std::time::Duration::from_millis(millis)),
+            DurationSetting::Text(ref text) => parse_duration(text),
+        }
+    }
+}
+
+/// A named set of events and settings, each of which is the same as the
+/// command line option of the same name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    /// What the profile is for, shown by `clickrs profiles list`.
+    pub description: Option<String>,
+    /// Keyboard events, as for `--keypress-and-interval`.
+    pub keys: Vec<String>,
+    /// Mouse events, as for `--mousebutton-and-interval`.
+    pub mouse: Vec<String>,
+    /// Other events, as for `--event`.
+    pub events: Vec<String>,
+    /// Text, as for `--type`.
+    #[serde(rename = "type")]
+    pub text: Vec<String>,
+    /// Sequences, as for `--sequence`.
+    pub sequences: Vec<String>,
+    /// A script to run, as for `clickrs run`, relative to the file the
+    /// profile is in.
+    pub script: Option<PathBuf>,
+    pub delay: Option<DurationSetting>,
+    pub pause_source: Option<String>,
+    pub backend: Option<String>,
+    pub catch_up: Option<String>,
+    pub seed: Option<u64>,
+    pub hold: Option<DurationSetting>,
+    pub max_events: Option<u64>,
+    pub max_runtime: Option<DurationSetting>,
+    pub x11_display: Option<String>,
+    pub dry_run_output: Option<PathBuf>,
+    pub unicode_input: Option<String>,
+    pub layout: Option<String>,
+    pub screen_size: Option<String>,
+}
+
+impl Profile {
+    /// Parse the profile's events. `base` is the directory its script is
+    /// relative to.
+    pub fn eventspecs(&self, base: &Path) -> Result<Vec<EventSpec>> {
+        let parse_all = |specs: &[String], parse: fn(&str) -> Result<EventSpec>| {
+            specs
+                .iter()
+                .map(|spec| parse(spec))
+                .collect::<Result<Vec<_>>>()
+        };
+        let mut eventspecs = parse_all(&self.mouse, EventSpec::parse_mouse)?;
+        eventspecs.extend(parse_all(&self.keys, EventSpec::parse_key)?);
+        eventspecs.extend(parse_all(&self.events, EventSpec::parse_event)?);
+        eventspecs.extend(parse_all(&self.text, EventSpec::parse_text)?);
+        eventspecs.extend(parse_all(&self.sequences, EventSpec::parse_sequence)?);
+        if let Some(ref path) = self.script {
+            eventspecs.extend(script::load(base.join(path))?);
+        }
+        Ok(eventspecs)
+    }
+
+    /// The number of events the profile has, not counting its script.
+    pub fn event_count(&self) -> usize {
+        self.keys.len()
+            + self.mouse.len()
+            + self.events.len()
+            + self.text.len()
+            + self.sequences.len()
+    }
+}
+
+/// A configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Profiles by name.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    /// The file the configuration was read from.
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl Config {
+    /// `clickrs/config.toml` in `XDG_CONFIG_HOME`, or in `~/.config` if that
+    /// isn't set.
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("clickrs").join("config.toml"))
+    }
+
+    /// Read the configuration file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read configuration file {:?}", path))?;
+        let yaml = path
+            .extension()
+            .is_some_and(|extension| extension == "yaml" || extension == "yml");
+        let mut config = if yaml {
+            Self::from_yaml(&text)
+        } else {
+            Self::from_toml(&text)
+        }
+        .map_err(|e| Error::Config(path.display().to_string(), e))?;
+        config.path = path.to_owned();
+        Ok(config)
+    }
+
+    fn from_toml(text: &str) -> std::result::Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.message().to_owned())
+    }
+
+    fn from_yaml(text: &str) -> std::result::Result<Self, String> {
+        // An empty file is an empty configuration, as it is in TOML
+        if text.trim().is_empty() {
+            return Ok(Config::default());
+        }
+        serde_yaml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// The profile named `name`.
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            Error::UnknownProfile(
+                name.to_owned(),
+                self.path.display().to_string(),
+                self.profiles.keys().cloned().collect(),
+            )
+            .into()
+        })
+    }
+
+    /// The directory that paths in the configuration are relative to.
+    pub fn base(&self) -> &Path {
+        self.path.parent().unwrap_or_else(|| Path::new("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const TOML: &str = r#"
+        [profiles.farming]
+        description = "Harvest and replant"
+        keys = ["e:500ms", "ctrl+s:5m"]
+        mouse = ["left@640,480:2s~500ms"]
+        sequences = ["ctrl+a, wait 50ms, ctrl+c:1m"]
+        delay = "5s"
+        pause-source = "none"
+        hold = 20
+        max-runtime = "2h"
+
+        [profiles.idle]
+        events = ["move:+1,+0:4m"]
+    "#;
+
+    const YAML: &str = "
+profiles:
+  farming:
+    description: Harvest and replant
+    keys: ['e:500ms', 'ctrl+s:5m']
+    mouse: ['left@640,480:2s~500ms']
+    sequences: ['ctrl+a, wait 50ms, ctrl+c:1m']
+    delay: 5s
+    pause-source: none
+    hold: 20
+    max-runtime: 2h
+  idle:
+    events: ['move:+1,+0:4m']
+";
+
+    #[test]
+    fn profiles_read_from_toml_and_yaml() {
+        let config = Config::from_toml(TOML).unwrap();
+        assert_eq!(config, Config::from_yaml(YAML).unwrap());
+        assert_eq!(
+            config.profiles.keys().collect::<Vec<_>>(),
+            vec!["farming", "idle"]
+        );
+        let farming = config.profile("farming").unwrap();
+        assert_eq!(farming.event_count(), 4);
+        assert_eq!(farming.eventspecs(Path::new(".")).unwrap().len(), 4);
+        assert_eq!(
+            farming.delay.as_ref().unwrap().duration().unwrap(),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            farming.hold.as_ref().unwrap().duration().unwrap(),
+            Duration::from_millis(20)
+        );
+        assert_eq!(farming.pause_source.as_deref(), Some("none"));
+        assert_eq!(farming.backend, None);
+        assert!(config.profile("fishing").is_err());
+    }
+
+    #[test]
+    fn bad_profiles_are_rejected() {
+        assert!(Config::from_toml("[profiles.a]\nkeys = \"a:1s\"").is_err());
+        assert!(Config::from_toml("[profiles.a]\nkey = [\"a:1s\"]").is_err());
+        assert!(Config::from_yaml("profiles:\n  a:\n    max-events: -1").is_err());
+        assert_eq!(Config::from_yaml("").unwrap(), Config::default());
+        let bad_event = Config::from_toml("[profiles.a]\nkeys = [\"a\"]").unwrap();
+        assert!(bad_event.profiles["a"].eventspecs(Path::new(".")).is_err());
+    }
+}
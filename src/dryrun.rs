@@ -0,0 +1,154 @@
+use std::io::Write;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+
+use crate::backend::InputBackend;
+use crate::chord::Chord;
+use crate::errors::Error;
+use crate::eventspec::{ScrollDirection, WHEEL_DETENT};
+use crate::keys;
+use crate::layout;
+
+/// Records input to a log instead of injecting it.
+///
+/// Each emitted key or button is written as a line with the time elapsed
+/// since the backend was created, so a schedule can be previewed, or run on
+/// machines with no input device at all. Keys are checked against the names
+/// every backend understands, and the keysyms of characters.
+pub struct DryRunBackend {
+    output: Box<dyn Write>,
+    created: Instant,
+    /// Where the pointer would be, once it's been moved somewhere absolute.
+    position: Option<(i32, i32)>,
+}
+
+impl std::fmt::Debug for DryRunBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "DryRun(created: {:?})", &self.created)
+    }
+}
+
+impl DryRunBackend {
+    /// Record input to `output`.
+    pub fn new(output: Box<dyn Write>) -> Self {
+        DryRunBackend {
+            output,
+            created: Instant::now(),
+            position: None,
+        }
+    }
+
+    /// Record input to stdout.
+    pub fn to_stdout() -> Self {
+        Self::new(Box::new(std::io::stdout()))
+    }
+
+    /// Record input to the file at `path`, truncating it if it exists.
+    pub fn to_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create dry-run output file {:?}", path))?;
+        Ok(Self::new(Box::new(std::io::LineWriter::new(file))))
+    }
+
+    fn record(&mut self, what: std::fmt::Arguments) -> Result<()> {
+        let elapsed = self.created.elapsed();
+        writeln!(
+            self.output,
+            "[{:>5}.{:03}s] {}",
+            elapsed.as_secs(),
+            elapsed.subsec_millis(),
+            what
+        )
+        .context("Failed to write dry-run output")
+    }
+}
+
+impl InputBackend for DryRunBackend {
+    fn key_down(&mut self, key: &str) -> Result<()> {
+        self.record(format_args!("key {} down", key))
+    }
+
+    fn key_up(&mut self, key: &str) -> Result<()> {
+        self.record(format_args!("key {} up", key))
+    }
+
+    fn button_down(&mut self, button: u8) -> Result<()> {
+        self.record(format_args!("button {} down", button))
+    }
+
+    fn button_up(&mut self, button: u8) -> Result<()> {
+        self.record(format_args!("button {} up", button))
+    }
+
+    fn emit_key(&mut self, key: &str) -> Result<()> {
+        self.record(format_args!("key {}", key))
+    }
+
+    fn emit_button(&mut self, button: u8) -> Result<()> {
+        self.record(format_args!("button {}", button))
+    }
+
+    fn move_by(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.position = self.position.map(|(x, y)| (x + dx, y + dy));
+        self.record(format_args!("move by {:+},{:+}", dx, dy))
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> Result<()> {
+        self.position = Some((x, y));
+        self.record(format_args!("move to {},{}", x, y))
+    }
+
+    fn scroll(&mut self, direction: ScrollDirection, amount: u32) -> Result<()> {
+        self.record(format_args!(
+            "scroll {} {}",
+            direction,
+            f64::from(amount) / f64::from(WHEEL_DETENT)
+        ))
+    }
+
+    fn type_unicode(&mut self, c: char) -> Result<()> {
+        self.record(format_args!("unicode {} (U+{:04X})", c, c as u32))
+    }
+
+    fn check_key(&mut self, key: &str) -> Result<()> {
+        let chord = Chord::parse(key)?;
+        if keys::lookup(&chord.key).is_none() && layout::keysym_char(&chord.key).is_none() {
+            return Err(Error::UnknownKey(chord.key.clone(), keys::suggest(&chord.key)).into());
+        }
+        Ok(())
+    }
+
+    fn check_unicode(&mut self, _c: char) -> Result<()> {
+        Ok(())
+    }
+
+    fn pointer_position(&mut self) -> Option<(i32, i32)> {
+        self.position
+    }
+
+    fn paused(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let mut dry_run = DryRunBackend::new(Box::new(std::io::sink()));
+        assert!(dry_run.check_key("KEY_F5").is_ok());
+        assert!(dry_run.check_key("ctrl+Return").is_ok());
+        assert!(dry_run.check_key("adiaeresis").is_ok());
+        let mut message = |s: &str| dry_run.check_key(s).unwrap_err().to_string();
+        assert_eq!(
+            message("Retrun"),
+            "Key Retrun is not known. Did you mean Return?"
+        );
+        assert!(message("ctrl+Escpe").starts_with("Key Escpe is not known. Did you mean Escape"));
+        assert_eq!(message("zzzzzzzz"), "Key zzzzzzzz is not known.");
+    }
+}
@@ -1,9 +1,46 @@
+/// Errors produced while parsing event specifications or setting up a backend.
 #[derive(Debug)]
 pub enum Error {
-    InputEventInterval(String, std::num::ParseIntError),
-    MouseEventButton(String, std::num::ParseIntError),
+    InputEventInterval(String, String),
+    InputEventOption(String, String),
+    MouseEventButton(String),
     MouseEventSpec(String),
+    MotionSpec(String),
+    EventSpec(String),
+    ScrollSpec(String),
+    TypeSpec(String),
+    SequenceSpec(String),
+    SequenceStep(String),
+    Script(String, usize, usize, String),
     KeyboardEventSpec(String),
+    KeyModifier(String),
+    UnknownKey(String, Vec<String>),
+    UnknownMouseButton(u8, Vec<String>),
+    UnicodeUnavailable(char),
+    CatchUpPolicy(String),
+    ScreenSize(String),
+    AbsoluteMoveUnavailable,
+    PauseSource(String),
+    UnicodeInputMethod(String),
+    KeyboardLayout(String, String),
+    NotOnLayout(String, String),
+    Config(String, String),
+    UnknownProfile(String, String, Vec<String>),
+    ProfileSetting(String, String, String),
+    NoConfigFile,
+    UnknownBackend(String, Vec<String>),
+    NoBackendAvailable,
+    #[cfg(feature = "x11")]
+    XDisplayOpen(String),
+}
+
+/// " Did you mean a, b or c?", or nothing if there's nothing to suggest.
+fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [only] => format!(" Did you mean {}?", only),
+        [rest @ .., last] => format!(" Did you mean {} or {}?", rest.join(", "), last),
+    }
 }
 
 impl Error {
@@ -12,15 +49,140 @@ impl Error {
             Error::InputEventInterval(s, e) => {
                 format!("Input event interval {} is not valid: {}", s, e)
             }
-            Error::MouseEventButton(s, e) => {
-                format!("Mouse button {} is not valid: {}", s, e)
+            Error::InputEventOption(s, e) => {
+                format!("Input event option {} is not valid: {}", s, e)
+            }
+            Error::MouseEventButton(s) => {
+                let names = crate::buttons::BUTTONS
+                    .iter()
+                    .map(|(name, _, _)| *name)
+                    .collect::<Vec<_>>();
+                format!(
+                    "Mouse button {} is not valid (expected a number from 1 to 255, or one of {}).",
+                    s,
+                    names.join(", ")
+                )
             }
             Error::MouseEventSpec(s) => {
                 format!("Mouse event specification {} is not valid.", s)
             }
+            Error::MotionSpec(s) => {
+                format!(
+                    "Pointer motion {} is not valid (expected X,Y for a screen position, or +X,-Y to move relative to the pointer).",
+                    s
+                )
+            }
+            Error::EventSpec(s) => {
+                format!(
+                    "Event specification {} is not valid (expected move:X,Y:INTERVAL, scroll:DIRECTION:DETENTS:INTERVAL or drag:BUTTON:[X,Y:]X,Y:INTERVAL).",
+                    s
+                )
+            }
+            Error::TypeSpec(s) => {
+                format!("Text specification {} is not valid (expected TEXT:INTERVAL).", s)
+            }
+            Error::SequenceSpec(s) => {
+                format!(
+                    "Sequence specification {} is not valid (expected STEP, STEP, ...:INTERVAL).",
+                    s
+                )
+            }
+            Error::SequenceStep(s) => {
+                format!(
+                    "Sequence step {} is not valid (expected wait DURATION, press KEY, click BUTTON [at X,Y], move X,Y [over DURATION], scroll DIRECTION DETENTS [over DURATION], drag BUTTON [from X,Y] to X,Y [over DURATION], type TEXT, or a key).",
+                    s
+                )
+            }
+            Error::Script(source, line, column, e) => {
+                format!("{}:{}:{}: {}", source, line, column, e)
+            }
+            Error::ScrollSpec(s) => {
+                format!(
+                    "Scroll event {} is not valid (expected a direction of up, down, left or right, and a positive number of detents).",
+                    s
+                )
+            }
             Error::KeyboardEventSpec(s) => {
                 format!("Keyboard event specification {} is not valid.", s)
             }
+            Error::KeyModifier(s) => {
+                format!(
+                    "Key modifier {} is not valid (expected shift, ctrl, alt, super, meta or altgr, optionally prefixed with l or r).",
+                    s
+                )
+            }
+            Error::UnknownKey(s, suggestions) => {
+                format!("Key {} is not known.{}", s, did_you_mean(suggestions))
+            }
+            Error::UnknownMouseButton(b, valid) => {
+                format!(
+                    "Mouse button {} is not available (expected {}).",
+                    b,
+                    valid.join(", ")
+                )
+            }
+            Error::UnicodeUnavailable(c) => {
+                format!(
+                    "Character {} (U+{:04X}) can't be typed with this backend.",
+                    c, *c as u32
+                )
+            }
+            Error::CatchUpPolicy(s) => {
+                format!("Catch-up policy {} is not valid.", s)
+            }
+            Error::ScreenSize(s) => {
+                format!("Screen size {} is not valid (expected WIDTHxHEIGHT).", s)
+            }
+            Error::AbsoluteMoveUnavailable => {
+                "Moving the pointer to a screen position with the uinput backend needs the screen size, which couldn't be found (give it with --screen-size).".to_owned()
+            }
+            Error::PauseSource(s) => {
+                format!("Pause source {} is not valid.", s)
+            }
+            Error::UnicodeInputMethod(s) => {
+                format!("Unicode input method {} is not valid.", s)
+            }
+            Error::KeyboardLayout(s, e) => {
+                format!("Keyboard layout {} could not be loaded: {}.", s, e)
+            }
+            Error::NotOnLayout(s, layout) => {
+                format!("Key {} is not on the {} keyboard layout.", s, layout)
+            }
+            Error::Config(path, e) => {
+                format!("Configuration file {} is not valid: {}", path, e)
+            }
+            Error::UnknownProfile(name, path, names) => {
+                if names.is_empty() {
+                    format!("Profile {} is not in {}, which has no profiles.", name, path)
+                } else {
+                    format!(
+                        "Profile {} is not in {} (expected {}).",
+                        name,
+                        path,
+                        names.join(", ")
+                    )
+                }
+            }
+            Error::ProfileSetting(name, setting, e) => {
+                format!("Setting {} in profile {} is not valid: {}", setting, name, e)
+            }
+            Error::NoConfigFile => {
+                "No configuration file to read profiles from (give one with --config, or set HOME).".to_owned()
+            }
+            Error::UnknownBackend(name, names) => {
+                format!(
+                    "Backend {} is not available (expected {}).",
+                    name,
+                    names.join(", ")
+                )
+            }
+            Error::NoBackendAvailable => {
+                "No usable input backend found (need write access to /dev/uinput or an X11 DISPLAY).".to_owned()
+            }
+            #[cfg(feature = "x11")]
+            Error::XDisplayOpen(s) => {
+                format!("Failed to open X11 display '{}'.", s)
+            }
         }
     }
 }
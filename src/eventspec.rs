@@ -1,47 +1,1225 @@
 use anyhow::Result;
 use log::debug;
 
+use crate::buttons;
+use crate::chord::Chord;
 use crate::errors::Error;
+use crate::keys;
+use crate::scheduler::CatchUp;
 
-pub(crate) enum EventSpec {
-    KeyboardEvent(String, std::time::Duration),
-    MouseEvent(u8, std::time::Duration),
+/// Random variation applied to an event's interval each time it's scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jitter {
+    /// Uniformly distributed within plus or minus the given amount.
+    Uniform(std::time::Duration),
+    /// Normally distributed with the given standard deviation.
+    Gaussian(std::time::Duration),
+}
+
+impl std::fmt::Display for Jitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Jitter::Uniform(ref range) => write!(f, "±{:?}", range),
+            Jitter::Gaussian(ref sigma) => write!(f, "σ={:?}", sigma),
+        }
+    }
+}
+
+/// When, how many times, and for how long an event fires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// Time between firings.
+    pub interval: std::time::Duration,
+    /// Random variation applied to each interval.
+    pub jitter: Option<Jitter>,
+    /// Number of times to fire before retiring the event, or `None` to repeat
+    /// forever.
+    pub count: Option<u64>,
+    /// How long to hold the key or button down, or `None` for the default.
+    pub hold: Option<std::time::Duration>,
+    /// Number of times the input is repeated each time the event fires, as
+    /// in a double click.
+    pub repeat: u32,
+    /// Time between the end of one repetition and the start of the next.
+    pub gap: std::time::Duration,
+    /// What to do with firings missed while falling behind, or `None` for
+    /// the default.
+    pub catch_up: Option<CatchUp>,
+}
+
+/// Time between the repetitions of a burst, unless it sets its own.
+pub const DEFAULT_GAP: std::time::Duration = std::time::Duration::from_millis(50);
+
+impl Schedule {
+    /// Fire forever, exactly every `interval`.
+    pub fn every(interval: std::time::Duration) -> Self {
+        Schedule {
+            interval,
+            jitter: None,
+            count: None,
+            hold: None,
+            repeat: 1,
+            gap: DEFAULT_GAP,
+            catch_up: None,
+        }
+    }
+}
+
+/// Where to move the pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    /// Move by this many pixels across and down.
+    By(i32, i32),
+    /// Move to this screen position.
+    To(i32, i32),
+}
+
+impl std::fmt::Display for Motion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Motion::By(dx, dy) => write!(f, "by {:+},{:+}", dx, dy),
+            Motion::To(x, y) => write!(f, "to {},{}", x, y),
+        }
+    }
+}
+
+impl std::str::FromStr for Motion {
+    type Err = Error;
+
+    /// Parse `X,Y` as a screen position, or `+X,-Y` as a relative move.
+    fn from_str(motion_str: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || Error::MotionSpec(motion_str.to_owned());
+        let (x_str, y_str) = motion_str.split_once(',').ok_or_else(invalid)?;
+        let (x_str, y_str) = (x_str.trim(), y_str.trim());
+        let x = x_str.parse::<i32>().map_err(|_| invalid())?;
+        let y = y_str.parse::<i32>().map_err(|_| invalid())?;
+        let signed = |s: &str| s.starts_with(['+', '-']);
+        match (signed(x_str), signed(y_str)) {
+            (true, true) => Ok(Motion::By(x, y)),
+            (false, false) => Ok(Motion::To(x, y)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// A pointer movement, made all at once or spread over a duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub motion: Motion,
+    /// How long the pointer takes to get there, or `None` to jump.
+    pub over: Option<std::time::Duration>,
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "move {}", self.motion)?;
+        if let Some(over) = self.over {
+            write!(f, " over {:?}", over)?;
+        }
+        Ok(())
+    }
+}
+
+/// Units a scroll wheel moves per detent (notch), as in the kernel's
+/// high-resolution wheel events.
+pub const WHEEL_DETENT: u32 = 120;
+
+/// Which way to scroll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl ScrollDirection {
+    pub const ALL: &'static [ScrollDirection] = &[
+        ScrollDirection::Up,
+        ScrollDirection::Down,
+        ScrollDirection::Left,
+        ScrollDirection::Right,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ScrollDirection::Up => "up",
+            ScrollDirection::Down => "down",
+            ScrollDirection::Left => "left",
+            ScrollDirection::Right => "right",
+        }
+    }
+}
+
+impl std::fmt::Display for ScrollDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A turn of the scroll wheel, made all at once or spread over a duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scroll {
+    pub direction: ScrollDirection,
+    /// How far to scroll, in [`WHEEL_DETENT`]ths of a detent.
+    pub amount: u32,
+    /// How long the scroll takes, or `None` to scroll all at once.
+    pub over: Option<std::time::Duration>,
+}
+
+impl std::fmt::Display for Scroll {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "scroll {} {}",
+            self.direction,
+            f64::from(self.amount) / f64::from(WHEEL_DETENT)
+        )?;
+        if let Some(over) = self.over {
+            write!(f, " over {:?}", over)?;
+        }
+        Ok(())
+    }
+}
+
+/// A mouse button click, optionally after moving the pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Click {
+    /// The button, numbered as in X11 (1 = left); see [`crate::buttons`].
+    pub button: u8,
+    /// Where to move the pointer before clicking.
+    pub at: Option<Motion>,
+}
+
+impl Click {
+    /// Click `button` wherever the pointer is.
+    pub fn button(button: u8) -> Self {
+        Click { button, at: None }
+    }
+}
+
+impl std::fmt::Display for Click {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "button {}", buttons::label(self.button))?;
+        if let Some(at) = self.at {
+            write!(f, " after moving {}", at)?;
+        }
+        Ok(())
+    }
+}
+
+/// A mouse button held down while the pointer moves, then released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Drag {
+    /// The button, numbered as in X11 (1 = left).
+    pub button: u8,
+    /// Where to move the pointer before pressing the button.
+    pub from: Option<Motion>,
+    /// Where to release the button.
+    pub to: Motion,
+    /// How long the drag takes, or `None` to jump straight to the end.
+    pub over: Option<std::time::Duration>,
+}
+
+impl std::fmt::Display for Drag {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "drag button {}", buttons::label(self.button))?;
+        if let Some(from) = self.from {
+            write!(f, " after moving {}, then", from)?;
+        }
+        write!(f, " {}", self.to)?;
+        if let Some(over) = self.over {
+            write!(f, " over {:?}", over)?;
+        }
+        Ok(())
+    }
+}
+
+/// Time between the keys of typed text, unless it sets its own.
+pub const DEFAULT_TYPE_DELAY: std::time::Duration = std::time::Duration::from_millis(30);
+
+/// Text typed one character at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text {
+    pub text: String,
+    /// Time between one key being released and the next being pressed.
+    pub delay: std::time::Duration,
+}
+
+/// How to type one character of a [`Text`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Keystroke {
+    /// Press the named key.
+    Key(String),
+    /// Enter a character that isn't on any key of a US layout, in whatever
+    /// way the backend can.
+    Unicode(char),
+}
+
+impl Text {
+    /// How to type each character: newlines and tabs are typed with Return
+    /// and Tab, characters on a US layout are keys of their own, and
+    /// anything else is entered as Unicode.
+    pub fn strokes(&self) -> impl Iterator<Item = Keystroke> + '_ {
+        self.text.chars().map(|c| match c {
+            '\n' => Keystroke::Key("Return".to_owned()),
+            '\t' => Keystroke::Key("Tab".to_owned()),
+            c if keys::lookup(&c.to_string()).is_some() => Keystroke::Key(c.to_string()),
+            c => Keystroke::Unicode(c),
+        })
+    }
+}
+
+impl std::fmt::Display for Text {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "type {:?}", self.text)
+    }
+}
+
+/// Replace the escapes `\n`, `\t`, `\\` and `\"` with the characters they
+/// stand for, leaving any other backslash as it is.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('\\') => unescaped.push('\\'),
+            Some('"') => unescaped.push('"'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// One step of a [`Sequence`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Press the named key, which may be a [`Chord`].
+    Key(String),
+    /// Click a mouse button.
+    Click(Click),
+    /// Move the pointer.
+    Move(Move),
+    /// Turn the scroll wheel.
+    Scroll(Scroll),
+    /// Drag with a mouse button.
+    Drag(Drag),
+    /// Type text.
+    Type(Text),
+    /// Do nothing for a while.
+    Wait(std::time::Duration),
+}
+
+impl Step {
+    /// Parse one step: `wait DURATION`, `press KEY` or just `KEY`,
+    /// `click BUTTON [at X,Y]`, `move X,Y [over DURATION]`,
+    /// `scroll DIRECTION DETENTS [over DURATION]`,
+    /// `drag BUTTON [from X,Y] to X,Y [over DURATION]`, or `type TEXT`, where
+    /// `TEXT` may be in double quotes and contain the escapes `\n`, `\t`,
+    /// `\\` and `\"`.
+    pub fn parse(step_str: &str) -> Result<Self> {
+        let step_str = step_str.trim();
+        let invalid = || Error::SequenceStep(step_str.to_owned());
+        let (word, rest) = step_str
+            .split_once(char::is_whitespace)
+            .map_or((step_str, ""), |(word, rest)| (word, rest.trim()));
+        let words = ["wait", "press", "click", "move", "scroll", "drag", "type"];
+        if step_str.is_empty() || (rest.is_empty() && words.contains(&word)) {
+            return Err(invalid().into());
+        }
+        // Everything but a click or a key can end with `over DURATION`
+        let over = |rest: &'_ str| -> Result<(String, Option<std::time::Duration>)> {
+            match rest.rsplit_once(" over ") {
+                Some((rest, over_str)) => Ok((
+                    rest.trim().to_owned(),
+                    Some(parse_duration(over_str.trim())?),
+                )),
+                None => Ok((rest.to_owned(), None)),
+            }
+        };
+        match word {
+            "wait" => Ok(Step::Wait(parse_duration(rest)?)),
+            "press" => Ok(Step::Key(parse_key_name(rest)?)),
+            "click" => {
+                let (button_str, at) = match rest.split_once(" at ") {
+                    Some((button_str, at_str)) => {
+                        (button_str.trim(), Some(at_str.parse::<Motion>()?))
+                    }
+                    None => (rest, None),
+                };
+                let button = buttons::parse(button_str)
+                    .ok_or_else(|| Error::MouseEventButton(button_str.to_owned()))?;
+                Ok(Step::Click(Click { button, at }))
+            }
+            "move" => {
+                let (motion_str, over) = over(rest)?;
+                let motion = motion_str.parse::<Motion>()?;
+                Ok(Step::Move(Move { motion, over }))
+            }
+            "scroll" => {
+                let (scroll_str, over) = over(rest)?;
+                let (direction_str, detents_str) = scroll_str
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(invalid)?;
+                let invalid_scroll = || Error::ScrollSpec(step_str.to_owned());
+                Ok(Step::Scroll(Scroll {
+                    direction: parse_direction(direction_str).ok_or_else(invalid_scroll)?,
+                    amount: parse_detents(detents_str.trim()).ok_or_else(invalid_scroll)?,
+                    over,
+                }))
+            }
+            "drag" => {
+                let (drag_str, over) = over(rest)?;
+                let (start_str, to_str) = drag_str.rsplit_once(" to ").ok_or_else(invalid)?;
+                let (button_str, from_str) = match start_str.split_once(" from ") {
+                    Some((button_str, from_str)) => (button_str.trim(), Some(from_str)),
+                    None => (start_str.trim(), None),
+                };
+                let button = buttons::parse(button_str)
+                    .ok_or_else(|| Error::MouseEventButton(button_str.to_owned()))?;
+                Ok(Step::Drag(Drag {
+                    button,
+                    from: from_str.map(str::parse::<Motion>).transpose()?,
+                    to: to_str.parse::<Motion>()?,
+                    over,
+                }))
+            }
+            "type" => {
+                let text = match rest.strip_prefix('"') {
+                    Some(quoted) => quoted.strip_suffix('"').ok_or_else(invalid)?,
+                    None => rest,
+                };
+                Ok(Step::Type(Text {
+                    text: unescape(text),
+                    delay: DEFAULT_TYPE_DELAY,
+                }))
+            }
+            _ if rest.is_empty() => Ok(Step::Key(parse_key_name(step_str)?)),
+            _ => Err(invalid().into()),
+        }
+    }
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Step::Key(ref key) => write!(f, "key {}", key),
+            Step::Click(ref click) => write!(f, "{}", click),
+            Step::Move(ref movement) => write!(f, "{}", movement),
+            Step::Scroll(ref scroll) => write!(f, "{}", scroll),
+            Step::Drag(ref drag) => write!(f, "{}", drag),
+            Step::Type(ref text) => write!(f, "{}", text),
+            Step::Wait(ref duration) => write!(f, "wait {:?}", duration),
+        }
+    }
+}
+
+/// Steps run one after another each time the sequence fires, e.g. select
+/// all, copy, switch windows and paste. Each step is finished before the
+/// next starts, and nothing else is sent until the last one is done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence {
+    pub steps: Vec<Step>,
+}
+
+impl std::fmt::Display for Sequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "sequence [")?;
+        for (i, step) in self.steps.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", step)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Split a list of steps at each comma, except those in double quotes and
+/// those inside the position a step moves, clicks or drags to, like `100,200`
+/// or `+5,-5`.
+fn split_steps(steps_str: &str) -> Vec<&str> {
+    let mut steps = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in steps_str.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted && !ends_in_x(&steps_str[start..i]) => {
+                steps.push(&steps_str[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    steps.push(&steps_str[start..]);
+    steps
+}
+
+/// Whether `step` stops after the X of a position, as `move 100` and
+/// `click left at 100` do before the `,200` that finishes them.
+fn ends_in_x(step: &str) -> bool {
+    let words: Vec<&str> = step.split_whitespace().collect();
+    match words[..] {
+        [.., word, x] if x.parse::<i32>().is_ok() => matches!(
+            (words[0], word),
+            ("move", "move") | ("click", "at") | ("drag", "from" | "to")
+        ),
+        _ => false,
+    }
+}
+
+/// A periodic input event, as described on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventSpec {
+    /// Press the named key on the given schedule.
+    KeyboardEvent(String, Schedule),
+    /// Click a mouse button on the given schedule.
+    MouseEvent(Click, Schedule),
+    /// Move the pointer on the given schedule.
+    MoveEvent(Move, Schedule),
+    /// Scroll on the given schedule.
+    ScrollEvent(Scroll, Schedule),
+    /// Drag with a mouse button on the given schedule.
+    DragEvent(Drag, Schedule),
+    /// Type text on the given schedule.
+    TypeEvent(Text, Schedule),
+    /// Run a sequence of steps on the given schedule.
+    SequenceEvent(Sequence, Schedule),
+}
+
+/// Parse a duration made of one or more `<number><unit>` components, e.g.
+/// `250ms`, `1.5s` or `1h30m`. A bare number is a count of milliseconds.
+pub fn parse_duration(duration_str: &str) -> Result<std::time::Duration> {
+    let invalid = |reason: String| Error::InputEventInterval(duration_str.to_owned(), reason);
+    if duration_str.is_empty() {
+        return Err(invalid("it is empty".to_owned()).into());
+    }
+
+    let mut nanos: f64 = 0.0;
+    let mut rest = duration_str;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let unit_len = rest[number_len..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len() - number_len);
+        let (component, remainder) = rest.split_at(number_len + unit_len);
+        let (number_str, unit) = component.split_at(number_len);
+        let number = number_str
+            .parse::<f64>()
+            .map_err(|_| invalid(format!("expected a number at \"{}\"", rest)))?;
+        let unit_nanos = match unit {
+            // A lone number is milliseconds, for compatibility with older versions
+            "" if component == duration_str => 1e6,
+            "" => return Err(invalid(format!("\"{}\" is missing a unit", component)).into()),
+            "ms" => 1e6,
+            "s" => 1e9,
+            "m" => 60e9,
+            "h" => 3600e9,
+            _ => {
+                return Err(invalid(format!(
+                    "unknown unit \"{}\" in \"{}\" (expected ms, s, m or h)",
+                    unit, component
+                ))
+                .into())
+            }
+        };
+        nanos += number * unit_nanos;
+        rest = remainder;
+    }
+    // Anything longer than u64 nanoseconds, some 584 years, would overflow
+    // the scheduler's deadlines
+    let nanos = nanos.round();
+    if nanos >= u64::MAX as f64 {
+        return Err(invalid("it is too long".to_owned()).into());
+    }
+    Ok(std::time::Duration::from_nanos(nanos as u64))
+}
+
+/// Parse an interval given either as a duration (see [`parse_duration`]) or
+/// as a rate such as `10cps` or `5hz`.
+pub fn parse_period(period_str: &str) -> Result<std::time::Duration> {
+    let lower = period_str.to_ascii_lowercase();
+    let rate_str = match lower
+        .strip_suffix("cps")
+        .or_else(|| lower.strip_suffix("hz"))
+    {
+        Some(rate_str) => rate_str,
+        None => {
+            let period = parse_duration(period_str)?;
+            // A zero interval would fire the event back to back
+            if period.is_zero() {
+                return Err(Error::InputEventInterval(
+                    period_str.to_owned(),
+                    "interval must be greater than zero".to_owned(),
+                )
+                .into());
+            }
+            return Ok(period);
+        }
+    };
+    let invalid = |reason: String| Error::InputEventInterval(period_str.to_owned(), reason);
+    let rate = rate_str
+        .parse::<f64>()
+        .map_err(|_| invalid(format!("rate \"{}\" is not a number", rate_str)))?;
+    if !(rate > 0.0 && rate.is_finite()) {
+        return Err(invalid("rate must be greater than zero".to_owned()).into());
+    }
+    let period = std::time::Duration::try_from_secs_f64(1.0 / rate)
+        .map_err(|_| invalid("rate is too low".to_owned()))?;
+    // A zero period would fire the event as fast as the queue can spin
+    if period.is_zero() {
+        return Err(invalid("rate is too high".to_owned()).into());
+    }
+    Ok(period)
+}
+
+/// Parse the value of a `repeat` option, or another option named `name`
+/// that means the same thing.
+fn parse_repeat(name: &str, value: &str) -> Result<u32> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|repeat| *repeat > 0)
+        .ok_or_else(|| {
+            Error::InputEventOption(
+                format!("{}={}", name, value),
+                format!("{} must be a positive integer", name),
+            )
+            .into()
+        })
+}
+
+/// Parse the name of a scroll direction.
+fn parse_direction(direction_str: &str) -> Option<ScrollDirection> {
+    ScrollDirection::ALL
+        .iter()
+        .copied()
+        .find(|direction| direction.name() == direction_str)
+}
+
+/// Parse a number of wheel detents, which may be fractional, as a scroll
+/// amount in [`WHEEL_DETENT`]ths of a detent.
+fn parse_detents(detents_str: &str) -> Option<u32> {
+    detents_str
+        .parse::<f64>()
+        .ok()
+        .map(|detents| (detents * f64::from(WHEEL_DETENT)).round())
+        .filter(|amount| *amount >= 1.0 && *amount <= f64::from(u32::MAX))
+        .map(|amount| amount as u32)
+}
+
+/// Check that `key_str` is a well-formed [`Chord`], and return it. Whether
+/// its key exists is up to the backend, which checks it before the run.
+fn parse_key_name(key_str: &str) -> Result<String> {
+    Chord::parse(key_str)?;
+    Ok(key_str.to_owned())
+}
+
+/// Parse `INTERVAL`, optionally followed by `~JITTER` for uniform jitter or
+/// `~gJITTER` for gaussian jitter, then any `,NAME=VALUE` options.
+///
+/// `count`, `hold`, `repeat`, `gap` and `catch-up` are handled here; options
+/// named in `extra_options` are returned for the caller to handle, and
+/// anything else is an error.
+pub(crate) fn parse_schedule<'a>(
+    schedule_str: &'a str,
+    extra_options: &[&str],
+) -> Result<(Schedule, Vec<(&'a str, &'a str)>)> {
+    let mut parts = schedule_str.split(',');
+    let interval_str = parts.next().unwrap_or_default();
+    let mut schedule = match interval_str.split_once('~') {
+        None => Schedule::every(parse_period(interval_str)?),
+        Some((interval_str, jitter_str)) => Schedule {
+            jitter: Some(match jitter_str.strip_prefix('g') {
+                Some(sigma_str) => Jitter::Gaussian(parse_duration(sigma_str)?),
+                None => Jitter::Uniform(parse_duration(jitter_str)?),
+            }),
+            ..Schedule::every(parse_period(interval_str)?)
+        },
+    };
+
+    let mut extra = Vec::new();
+    for option in parts {
+        let invalid = |reason: &str| Error::InputEventOption(option.to_owned(), reason.to_owned());
+        let (name, value) = option
+            .split_once('=')
+            .ok_or_else(|| invalid("expected NAME=VALUE"))?;
+        match name {
+            "count" => {
+                let count = value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .ok_or_else(|| invalid("count must be a positive integer"))?;
+                schedule.count = Some(count);
+            }
+            "hold" => schedule.hold = Some(parse_duration(value)?),
+            "repeat" => schedule.repeat = parse_repeat(name, value)?,
+            "gap" => schedule.gap = parse_duration(value)?,
+            "catch-up" => schedule.catch_up = Some(value.parse()?),
+            _ if extra_options.contains(&name) => extra.push((name, value)),
+            _ => {
+                let expected = ["count", "hold", "repeat", "gap", "catch-up"]
+                    .iter()
+                    .chain(extra_options)
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(invalid(&format!("unknown option (expected {})", expected)).into());
+            }
+        }
+    }
+    Ok((schedule, extra))
 }
 
 impl EventSpec {
+    /// Parse a `BUTTON[@X,Y]:INTERVAL[~[g]JITTER][,NAME=VALUE...]` mouse
+    /// event specification, where `BUTTON` is a number or a name from
+    /// [`crate::buttons`], with the options of every event, and `clicks=N` as
+    /// another name for `repeat=N`, e.g. `1:5s,clicks=2` to double click. With
+    /// `@X,Y` (or ` at X,Y`), the pointer is moved before each click, as for
+    /// a [`Motion`].
     pub fn parse_mouse(arg: &str) -> Result<Self> {
         debug!("Parsing mouse str option {}.", arg);
 
-        if let Some((button_str, interval_str)) = arg.split_once(':') {
-            let button = button_str
-                .parse::<u8>()
-                .map_err(|e| Error::MouseEventButton(button_str.to_owned(), e))?;
-            let interval = interval_str
-                .parse::<u64>()
-                .map_err(|e| Error::InputEventInterval(interval_str.to_owned(), e))?;
-            Ok(EventSpec::MouseEvent(
-                button,
-                std::time::Duration::from_millis(interval),
-            ))
+        if let Some((click_str, interval_str)) = arg.split_once(':') {
+            let (button_str, at) = match click_str
+                .split_once('@')
+                .or_else(|| click_str.split_once(" at "))
+            {
+                Some((button_str, at_str)) => (button_str.trim(), Some(at_str.parse::<Motion>()?)),
+                None => (click_str, None),
+            };
+            let button = buttons::parse(button_str)
+                .ok_or_else(|| Error::MouseEventButton(button_str.to_owned()))?;
+            let (mut schedule, extra) = parse_schedule(interval_str, &["clicks"])?;
+            for (name, value) in extra {
+                schedule.repeat = parse_repeat(name, value)?;
+            }
+            Ok(EventSpec::MouseEvent(Click { button, at }, schedule))
         } else {
             Err(Error::MouseEventSpec(arg.to_owned()).into())
         }
     }
 
+    /// Parse a `move:X,Y:INTERVAL[~[g]JITTER][,NAME=VALUE...]` pointer
+    /// movement specification, where `X,Y` is a [`Motion`], or a
+    /// `scroll:DIRECTION:DETENTS:INTERVAL[~[g]JITTER][,NAME=VALUE...]` scroll
+    /// specification, where `DETENTS` may be fractional, or a
+    /// `drag:BUTTON:[FROM:]TO:INTERVAL[~[g]JITTER][,NAME=VALUE...]` drag
+    /// specification, where `FROM` and `TO` are [`Motion`]s. Besides the
+    /// options of every event, they take `over=DURATION` to spread the
+    /// movement out instead of making it all at once.
+    pub fn parse_event(arg: &str) -> Result<Self> {
+        debug!("Parsing event str option {}.", arg);
+
+        let invalid = || Error::EventSpec(arg.to_owned());
+        let (kind, rest) = arg.split_once(':').ok_or_else(invalid)?;
+        let over = |extra: Vec<(&str, &str)>| -> Result<Option<std::time::Duration>> {
+            extra
+                .last()
+                .map(|(_, value)| parse_duration(value))
+                .transpose()
+        };
+        match kind {
+            "move" => {
+                let (motion_str, interval_str) = rest.split_once(':').ok_or_else(invalid)?;
+                let motion = motion_str.parse::<Motion>()?;
+                let (schedule, extra) = parse_schedule(interval_str, &["over"])?;
+                let over = over(extra)?;
+                Ok(EventSpec::MoveEvent(Move { motion, over }, schedule))
+            }
+            "scroll" => {
+                let mut parts = rest.splitn(3, ':');
+                let (direction_str, detents_str, interval_str) =
+                    match (parts.next(), parts.next(), parts.next()) {
+                        (Some(direction), Some(detents), Some(interval)) => {
+                            (direction, detents, interval)
+                        }
+                        _ => return Err(invalid().into()),
+                    };
+                let invalid_scroll = || Error::ScrollSpec(arg.to_owned());
+                let direction = parse_direction(direction_str).ok_or_else(invalid_scroll)?;
+                let amount = parse_detents(detents_str).ok_or_else(invalid_scroll)?;
+                let (schedule, extra) = parse_schedule(interval_str, &["over"])?;
+                let over = over(extra)?;
+                Ok(EventSpec::ScrollEvent(
+                    Scroll {
+                        direction,
+                        amount,
+                        over,
+                    },
+                    schedule,
+                ))
+            }
+            "drag" => {
+                let parts = rest.split(':').collect::<Vec<_>>();
+                let (button_str, from_str, to_str, interval_str) = match parts[..] {
+                    [button, to, interval] => (button, None, to, interval),
+                    [button, from, to, interval] => (button, Some(from), to, interval),
+                    _ => return Err(invalid().into()),
+                };
+                let button = buttons::parse(button_str)
+                    .ok_or_else(|| Error::MouseEventButton(button_str.to_owned()))?;
+                let from = from_str.map(str::parse::<Motion>).transpose()?;
+                let to = to_str.parse::<Motion>()?;
+                let (schedule, extra) = parse_schedule(interval_str, &["over"])?;
+                let over = over(extra)?;
+                Ok(EventSpec::DragEvent(
+                    Drag {
+                        button,
+                        from,
+                        to,
+                        over,
+                    },
+                    schedule,
+                ))
+            }
+            _ => Err(invalid().into()),
+        }
+    }
+
+    /// Parse a `KEY:INTERVAL[~[g]JITTER][,NAME=VALUE...]` keyboard event
+    /// specification, with options `count=N`, `hold=DURATION`, and `repeat=N`
+    /// and `gap=DURATION` to press it several times in a row. `KEY` may
+    /// be a [`Chord`] such as `ctrl+shift+t`.
     pub fn parse_key(arg: &str) -> Result<Self> {
         debug!("Parsing keyboard str option {}.", arg);
 
         if let Some((key_str, interval_str)) = arg.split_once(':') {
-            let key = key_str.to_owned();
-            let interval = interval_str
-                .parse::<u64>()
-                .map_err(|e| Error::InputEventInterval(interval_str.to_owned(), e))?;
-            Ok(EventSpec::KeyboardEvent(
-                key,
-                std::time::Duration::from_millis(interval),
-            ))
+            let key = parse_key_name(key_str)?;
+            let (schedule, _) = parse_schedule(interval_str, &[])?;
+            Ok(EventSpec::KeyboardEvent(key, schedule))
         } else {
             Err(Error::KeyboardEventSpec(arg.to_owned()).into())
         }
     }
+
+    /// Parse a `TEXT:INTERVAL[~[g]JITTER][,NAME=VALUE...]` text
+    /// specification, where `TEXT` runs up to the last `:` and may contain
+    /// the escapes `\n`, `\t` and `\\`. Besides the options of every event,
+    /// it takes `delay=DURATION` for the time between keys.
+    pub fn parse_text(arg: &str) -> Result<Self> {
+        debug!("Parsing text str option {}.", arg);
+
+        let (text_str, interval_str) = arg
+            .rsplit_once(':')
+            .filter(|(text_str, _)| !text_str.is_empty())
+            .ok_or_else(|| Error::TypeSpec(arg.to_owned()))?;
+        let (schedule, extra) = parse_schedule(interval_str, &["delay"])?;
+        let mut text = Text {
+            text: unescape(text_str),
+            delay: DEFAULT_TYPE_DELAY,
+        };
+        for (_, value) in extra {
+            text.delay = parse_duration(value)?;
+        }
+        Ok(EventSpec::TypeEvent(text, schedule))
+    }
+
+    /// Parse a `STEP, STEP, ...:INTERVAL[~[g]JITTER][,NAME=VALUE...]`
+    /// sequence specification, where each `STEP` is as for [`Step::parse`],
+    /// e.g. `ctrl+a, wait 50ms, ctrl+c, wait 200ms, alt+Tab, ctrl+v:1m`.
+    /// Steps are separated by commas, except those in quoted text or between
+    /// the numbers of a position, and the steps run up to the last `:`. With
+    /// `repeat=N`, the whole sequence is run `N` times in a row, and `hold`
+    /// applies to each of its keys and clicks.
+    pub fn parse_sequence(arg: &str) -> Result<Self> {
+        debug!("Parsing sequence str option {}.", arg);
+
+        let (steps_str, interval_str) = arg
+            .rsplit_once(':')
+            .filter(|(steps_str, _)| !steps_str.trim().is_empty())
+            .ok_or_else(|| Error::SequenceSpec(arg.to_owned()))?;
+        let steps = split_steps(steps_str)
+            .into_iter()
+            .map(Step::parse)
+            .collect::<Result<Vec<_>>>()?;
+        let (schedule, _) = parse_schedule(interval_str, &[])?;
+        Ok(EventSpec::SequenceEvent(Sequence { steps }, schedule))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn millis(s: &str) -> u128 {
+        parse_period(s).unwrap().as_millis()
+    }
+
+    #[test]
+    fn durations_accept_units() {
+        assert_eq!(millis("250"), 250);
+        assert_eq!(millis("250ms"), 250);
+        assert_eq!(millis("1.5s"), 1500);
+        assert_eq!(millis("2m"), 120_000);
+        assert_eq!(millis("1h30m"), 5_400_000);
+        assert_eq!(millis("1m0.5s"), 60_500);
+    }
+
+    #[test]
+    fn rates_become_periods() {
+        assert_eq!(millis("10cps"), 100);
+        assert_eq!(millis("5Hz"), 200);
+        assert_eq!(millis("0.5hz"), 2000);
+    }
+
+    #[test]
+    fn bad_durations_name_the_token() {
+        let message = |s: &str| parse_period(s).unwrap_err().to_string();
+        assert!(message("1h3x").contains("\"x\" in \"3x\""));
+        assert!(message("1h30").contains("\"30\" is missing a unit"));
+        assert!(message("fast").contains("expected a number at \"fast\""));
+        assert!(message("0hz").contains("greater than zero"));
+        assert!(message("").contains("empty"));
+    }
+
+    #[test]
+    fn out_of_range_intervals_are_rejected() {
+        let message = |s: &str| parse_period(s).unwrap_err().to_string();
+        assert!(message("1e-30hz").contains("rate is too low"));
+        assert!(message("1e300hz").contains("rate is too high"));
+        assert!(message("1e10hz").contains("rate is too high"));
+        assert!(message("0").contains("greater than zero"));
+        assert!(message("0ms").contains("greater than zero"));
+        assert!(message("0s").contains("greater than zero"));
+        assert!(message("0.0000001ms").contains("greater than zero"));
+        assert_eq!(millis("1000cps"), 1);
+        assert!(message("99999999999999999999999h").contains("too long"));
+        assert!(message("5124096h").contains("too long"));
+        assert!(message(&"9".repeat(400)).contains("too long"));
+        assert_eq!(
+            parse_duration("5124095h").unwrap(),
+            Duration::from_secs(5_124_095 * 3600)
+        );
+    }
+
+    #[test]
+    fn specs_parse_interval_and_jitter() {
+        assert_eq!(
+            EventSpec::parse_key("space:1.5s~g200ms").unwrap(),
+            EventSpec::KeyboardEvent(
+                "space".to_owned(),
+                Schedule {
+                    jitter: Some(Jitter::Gaussian(Duration::from_millis(200))),
+                    ..Schedule::every(Duration::from_millis(1500))
+                }
+            )
+        );
+        assert_eq!(
+            EventSpec::parse_mouse("1:20m").unwrap(),
+            EventSpec::MouseEvent(Click::button(1), Schedule::every(Duration::from_secs(1200)))
+        );
+    }
+
+    #[test]
+    fn specs_parse_options() {
+        assert_eq!(
+            EventSpec::parse_key("a:500~100,count=20").unwrap(),
+            EventSpec::KeyboardEvent(
+                "a".to_owned(),
+                Schedule {
+                    interval: Duration::from_millis(500),
+                    jitter: Some(Jitter::Uniform(Duration::from_millis(100))),
+                    count: Some(20),
+                    ..Schedule::every(Duration::from_millis(500))
+                }
+            )
+        );
+        assert_eq!(
+            EventSpec::parse_key("space:1s,hold=80ms").unwrap(),
+            EventSpec::KeyboardEvent(
+                "space".to_owned(),
+                Schedule {
+                    hold: Some(Duration::from_millis(80)),
+                    ..Schedule::every(Duration::from_secs(1))
+                }
+            )
+        );
+        assert_eq!(
+            EventSpec::parse_key("a:1s,catch-up=skip").unwrap(),
+            EventSpec::KeyboardEvent(
+                "a".to_owned(),
+                Schedule {
+                    catch_up: Some(CatchUp::Skip),
+                    ..Schedule::every(Duration::from_secs(1))
+                }
+            )
+        );
+        let message = |s: &str| EventSpec::parse_key(s).unwrap_err().to_string();
+        assert!(message("a:500,count=0").contains("count=0"));
+        assert!(message("a:500,catch-up=later").contains("later"));
+        assert!(message("a:500,count").contains("NAME=VALUE"));
+        assert!(message("a:500,times=3").contains("times=3"));
+    }
+
+    #[test]
+    fn bursts_parse() {
+        let schedule = |spec: &str| match EventSpec::parse_mouse(spec).unwrap() {
+            EventSpec::MouseEvent(_, schedule) => schedule,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(
+            schedule("1:5000,clicks=2,gap=60ms"),
+            Schedule {
+                repeat: 2,
+                gap: Duration::from_millis(60),
+                ..Schedule::every(Duration::from_secs(5))
+            }
+        );
+        assert_eq!(schedule("1:5000,repeat=3").repeat, 3);
+        assert_eq!(schedule("1:5000").repeat, 1);
+        assert!(EventSpec::parse_mouse("1:5000,clicks=0").is_err());
+        assert!(EventSpec::parse_key("a:1s,clicks=2").is_err());
+        assert_eq!(
+            EventSpec::parse_key("a:1s,repeat=5,gap=10ms").unwrap(),
+            EventSpec::KeyboardEvent(
+                "a".to_owned(),
+                Schedule {
+                    repeat: 5,
+                    gap: Duration::from_millis(10),
+                    ..Schedule::every(Duration::from_secs(1))
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn moves_parse() {
+        assert_eq!(
+            EventSpec::parse_event("move:+10,-5:1s").unwrap(),
+            EventSpec::MoveEvent(
+                Move {
+                    motion: Motion::By(10, -5),
+                    over: None
+                },
+                Schedule::every(Duration::from_secs(1))
+            )
+        );
+        assert_eq!(
+            EventSpec::parse_event("move:640,480:1m,over=250ms,count=2").unwrap(),
+            EventSpec::MoveEvent(
+                Move {
+                    motion: Motion::To(640, 480),
+                    over: Some(Duration::from_millis(250))
+                },
+                Schedule {
+                    count: Some(2),
+                    ..Schedule::every(Duration::from_secs(60))
+                }
+            )
+        );
+        assert!(EventSpec::parse_event("move:+10,480:1s").is_err());
+        assert!(EventSpec::parse_event("move:10:1s").is_err());
+        assert!(EventSpec::parse_event("wiggle:10,10:1s").is_err());
+        assert!(EventSpec::parse_key("a:1s,over=1s").is_err());
+    }
+
+    #[test]
+    fn scrolls_parse() {
+        assert_eq!(
+            EventSpec::parse_event("scroll:up:3:500ms").unwrap(),
+            EventSpec::ScrollEvent(
+                Scroll {
+                    direction: ScrollDirection::Up,
+                    amount: 360,
+                    over: None
+                },
+                Schedule::every(Duration::from_millis(500))
+            )
+        );
+        assert_eq!(
+            EventSpec::parse_event("scroll:left:0.25:1s,over=100ms").unwrap(),
+            EventSpec::ScrollEvent(
+                Scroll {
+                    direction: ScrollDirection::Left,
+                    amount: 30,
+                    over: Some(Duration::from_millis(100))
+                },
+                Schedule::every(Duration::from_secs(1))
+            )
+        );
+        assert!(EventSpec::parse_event("scroll:sideways:3:1s").is_err());
+        assert!(EventSpec::parse_event("scroll:down:0:1s").is_err());
+        assert!(EventSpec::parse_event("scroll:down:-1:1s").is_err());
+        assert!(EventSpec::parse_event("scroll:down:1s").is_err());
+    }
+
+    #[test]
+    fn drags_parse() {
+        assert_eq!(
+            EventSpec::parse_event("drag:1:100,100:400,300:5s,over=500ms").unwrap(),
+            EventSpec::DragEvent(
+                Drag {
+                    button: 1,
+                    from: Some(Motion::To(100, 100)),
+                    to: Motion::To(400, 300),
+                    over: Some(Duration::from_millis(500))
+                },
+                Schedule::every(Duration::from_secs(5))
+            )
+        );
+        assert_eq!(
+            EventSpec::parse_event("drag:right:+50,+0:1s").unwrap(),
+            EventSpec::DragEvent(
+                Drag {
+                    button: 3,
+                    from: None,
+                    to: Motion::By(50, 0),
+                    over: None
+                },
+                Schedule::every(Duration::from_secs(1))
+            )
+        );
+        assert!(EventSpec::parse_event("drag:0:10,10:1s").is_err());
+        assert!(EventSpec::parse_event("drag:1:1s").is_err());
+        assert!(EventSpec::parse_event("drag:1:1,1:2,2:3,3:1s").is_err());
+    }
+
+    #[test]
+    fn text_parses_with_escapes() {
+        let text = |spec: &str| match EventSpec::parse_text(spec).unwrap() {
+            EventSpec::TypeEvent(text, schedule) => (text, schedule),
+            other => panic!("{:?}", other),
+        };
+        let (hello, schedule) = text("hello world\\n:30000");
+        assert_eq!(hello.text, "hello world\n");
+        assert_eq!(hello.delay, DEFAULT_TYPE_DELAY);
+        assert_eq!(schedule, Schedule::every(Duration::from_secs(30)));
+        let key = |name: &str| Keystroke::Key(name.to_owned());
+        assert_eq!(
+            hello.strokes().skip(9).collect::<Vec<_>>(),
+            vec![key("l"), key("d"), key("Return")]
+        );
+
+        let (tabbed, _) = text("a:b\\tc\\\\d\\x:1s,delay=5ms");
+        assert_eq!(tabbed.text, "a:b\tc\\d\\x");
+        assert_eq!(tabbed.delay, Duration::from_millis(5));
+        assert_eq!(tabbed.strokes().nth(3), Some(key("Tab")));
+
+        assert!(EventSpec::parse_text("no interval").is_err());
+        assert!(EventSpec::parse_text(":1s").is_err());
+
+        let (cafe, _) = text("café ☕:1s");
+        assert_eq!(
+            cafe.strokes().skip(3).collect::<Vec<_>>(),
+            vec![Keystroke::Unicode('é'), key(" "), Keystroke::Unicode('☕')]
+        );
+    }
+
+    #[test]
+    fn sequences_parse() {
+        let steps = |spec: &str| match EventSpec::parse_sequence(spec).unwrap() {
+            EventSpec::SequenceEvent(sequence, _) => sequence.steps,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(
+            steps("ctrl+a, wait 50ms, press ctrl+c,alt+Tab:1m"),
+            vec![
+                Step::Key("ctrl+a".to_owned()),
+                Step::Wait(Duration::from_millis(50)),
+                Step::Key("ctrl+c".to_owned()),
+                Step::Key("alt+Tab".to_owned()),
+            ]
+        );
+        assert_eq!(
+            steps("1,2:1s"),
+            vec![Step::Key("1".to_owned()), Step::Key("2".to_owned())]
+        );
+        assert_eq!(
+            steps("click right at 100,200, move +5,-5 over 1s, scroll down 0.5:1s"),
+            vec![
+                Step::Click(Click {
+                    button: 3,
+                    at: Some(Motion::To(100, 200)),
+                }),
+                Step::Move(Move {
+                    motion: Motion::By(5, -5),
+                    over: Some(Duration::from_secs(1)),
+                }),
+                Step::Scroll(Scroll {
+                    direction: ScrollDirection::Down,
+                    amount: 60,
+                    over: None,
+                }),
+            ]
+        );
+        assert_eq!(
+            steps("drag left from 1,2 to 3,4 over 50ms, type \"a, \\\"b\\\":c\", Return:5s"),
+            vec![
+                Step::Drag(Drag {
+                    button: 1,
+                    from: Some(Motion::To(1, 2)),
+                    to: Motion::To(3, 4),
+                    over: Some(Duration::from_millis(50)),
+                }),
+                Step::Type(Text {
+                    text: "a, \"b\":c".to_owned(),
+                    delay: DEFAULT_TYPE_DELAY,
+                }),
+                Step::Key("Return".to_owned()),
+            ]
+        );
+
+        assert!(EventSpec::parse_sequence("ctrl+a, wait:1s").is_err());
+        assert!(EventSpec::parse_sequence("ctrl+a, , b:1s").is_err());
+        assert!(EventSpec::parse_sequence("click 0:1s").is_err());
+        assert!(EventSpec::parse_sequence("drag left 3,4:1s").is_err());
+        assert!(EventSpec::parse_sequence("jump 3:1s").is_err());
+        assert!(EventSpec::parse_sequence("a, b").is_err());
+    }
+
+    #[test]
+    fn clicks_parse_positions() {
+        let click = |spec: &str| match EventSpec::parse_mouse(spec).unwrap() {
+            EventSpec::MouseEvent(click, _) => click,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(click("3:1s"), Click::button(3));
+        let at = Some(Motion::To(640, 480));
+        assert_eq!(click("1@640,480:1s"), Click { button: 1, at });
+        assert_eq!(click("1 at 640,480:1s"), Click { button: 1, at });
+        assert_eq!(click("2@-1,+1:1s").at, Some(Motion::By(-1, 1)));
+    }
+
+    #[test]
+    fn clicks_parse_button_names() {
+        let button = |spec: &str| match EventSpec::parse_mouse(spec).unwrap() {
+            EventSpec::MouseEvent(click, _) => click.button,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(button("left:1s"), 1);
+        assert_eq!(button("side@10,10:1s"), 8);
+        assert_eq!(button("9:1s"), 9);
+        assert!(EventSpec::parse_mouse("0:1s").is_err());
+        assert!(EventSpec::parse_mouse("thumb:1s").is_err());
+    }
+
+    #[test]
+    fn keys_are_left_to_the_backend() {
+        assert!(EventSpec::parse_key("KEY_F5:1s").is_ok());
+        assert!(EventSpec::parse_key("ctrl+Return:1s").is_ok());
+        // Only the backend knows which keysyms it can send
+        assert!(EventSpec::parse_key("XF86AudioPlay:1s").is_ok());
+        assert!(EventSpec::parse_key("ctrl+:1s").is_err());
+        assert!(EventSpec::parse_key("hyperr+a:1s").is_err());
+    }
 }
@@ -0,0 +1,804 @@
+//! Key names understood by every backend.
+//!
+//! Keys can be named either as in the kernel's `linux/input-event-codes.h`
+//! (`KEY_F5`, `KEY_ENTER`), or as X keysyms (`F5`, `Return`, `a`, `exclam`),
+//! and printable ASCII characters can also be given as themselves (`!`). X
+//! keysyms are mapped to kernel keys as they would be on a US layout.
+
+/// A kernel key code, and whether shift has to be held with it to produce
+/// the key that was asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCode {
+    pub code: u16,
+    pub shift: bool,
+}
+
+/// Kernel key names, with their codes.
+pub const EVDEV_KEYS: &[(&str, u16)] = &[
+    ("KEY_ESC", 1),
+    ("KEY_1", 2),
+    ("KEY_2", 3),
+    ("KEY_3", 4),
+    ("KEY_4", 5),
+    ("KEY_5", 6),
+    ("KEY_6", 7),
+    ("KEY_7", 8),
+    ("KEY_8", 9),
+    ("KEY_9", 10),
+    ("KEY_0", 11),
+    ("KEY_MINUS", 12),
+    ("KEY_EQUAL", 13),
+    ("KEY_BACKSPACE", 14),
+    ("KEY_TAB", 15),
+    ("KEY_Q", 16),
+    ("KEY_W", 17),
+    ("KEY_E", 18),
+    ("KEY_R", 19),
+    ("KEY_T", 20),
+    ("KEY_Y", 21),
+    ("KEY_U", 22),
+    ("KEY_I", 23),
+    ("KEY_O", 24),
+    ("KEY_P", 25),
+    ("KEY_LEFTBRACE", 26),
+    ("KEY_RIGHTBRACE", 27),
+    ("KEY_ENTER", 28),
+    ("KEY_LEFTCTRL", 29),
+    ("KEY_A", 30),
+    ("KEY_S", 31),
+    ("KEY_D", 32),
+    ("KEY_F", 33),
+    ("KEY_G", 34),
+    ("KEY_H", 35),
+    ("KEY_J", 36),
+    ("KEY_K", 37),
+    ("KEY_L", 38),
+    ("KEY_SEMICOLON", 39),
+    ("KEY_APOSTROPHE", 40),
+    ("KEY_GRAVE", 41),
+    ("KEY_LEFTSHIFT", 42),
+    ("KEY_BACKSLASH", 43),
+    ("KEY_Z", 44),
+    ("KEY_X", 45),
+    ("KEY_C", 46),
+    ("KEY_V", 47),
+    ("KEY_B", 48),
+    ("KEY_N", 49),
+    ("KEY_M", 50),
+    ("KEY_COMMA", 51),
+    ("KEY_DOT", 52),
+    ("KEY_SLASH", 53),
+    ("KEY_RIGHTSHIFT", 54),
+    ("KEY_KPASTERISK", 55),
+    ("KEY_LEFTALT", 56),
+    ("KEY_SPACE", 57),
+    ("KEY_CAPSLOCK", 58),
+    ("KEY_F1", 59),
+    ("KEY_F2", 60),
+    ("KEY_F3", 61),
+    ("KEY_F4", 62),
+    ("KEY_F5", 63),
+    ("KEY_F6", 64),
+    ("KEY_F7", 65),
+    ("KEY_F8", 66),
+    ("KEY_F9", 67),
+    ("KEY_F10", 68),
+    ("KEY_NUMLOCK", 69),
+    ("KEY_SCROLLLOCK", 70),
+    ("KEY_KP7", 71),
+    ("KEY_KP8", 72),
+    ("KEY_KP9", 73),
+    ("KEY_KPMINUS", 74),
+    ("KEY_KP4", 75),
+    ("KEY_KP5", 76),
+    ("KEY_KP6", 77),
+    ("KEY_KPPLUS", 78),
+    ("KEY_KP1", 79),
+    ("KEY_KP2", 80),
+    ("KEY_KP3", 81),
+    ("KEY_KP0", 82),
+    ("KEY_KPDOT", 83),
+    ("KEY_ZENKAKUHANKAKU", 85),
+    ("KEY_102ND", 86),
+    ("KEY_F11", 87),
+    ("KEY_F12", 88),
+    ("KEY_RO", 89),
+    ("KEY_KATAKANA", 90),
+    ("KEY_HIRAGANA", 91),
+    ("KEY_HENKAN", 92),
+    ("KEY_KATAKANAHIRAGANA", 93),
+    ("KEY_MUHENKAN", 94),
+    ("KEY_KPJPCOMMA", 95),
+    ("KEY_KPENTER", 96),
+    ("KEY_RIGHTCTRL", 97),
+    ("KEY_KPSLASH", 98),
+    ("KEY_SYSRQ", 99),
+    ("KEY_RIGHTALT", 100),
+    ("KEY_LINEFEED", 101),
+    ("KEY_HOME", 102),
+    ("KEY_UP", 103),
+    ("KEY_PAGEUP", 104),
+    ("KEY_LEFT", 105),
+    ("KEY_RIGHT", 106),
+    ("KEY_END", 107),
+    ("KEY_DOWN", 108),
+    ("KEY_PAGEDOWN", 109),
+    ("KEY_INSERT", 110),
+    ("KEY_DELETE", 111),
+    ("KEY_MACRO", 112),
+    ("KEY_MUTE", 113),
+    ("KEY_VOLUMEDOWN", 114),
+    ("KEY_VOLUMEUP", 115),
+    ("KEY_POWER", 116),
+    ("KEY_KPEQUAL", 117),
+    ("KEY_KPPLUSMINUS", 118),
+    ("KEY_PAUSE", 119),
+    ("KEY_SCALE", 120),
+    ("KEY_KPCOMMA", 121),
+    ("KEY_HANGEUL", 122),
+    ("KEY_HANGUEL", 122),
+    ("KEY_HANJA", 123),
+    ("KEY_YEN", 124),
+    ("KEY_LEFTMETA", 125),
+    ("KEY_RIGHTMETA", 126),
+    ("KEY_COMPOSE", 127),
+    ("KEY_STOP", 128),
+    ("KEY_AGAIN", 129),
+    ("KEY_PROPS", 130),
+    ("KEY_UNDO", 131),
+    ("KEY_FRONT", 132),
+    ("KEY_COPY", 133),
+    ("KEY_OPEN", 134),
+    ("KEY_PASTE", 135),
+    ("KEY_FIND", 136),
+    ("KEY_CUT", 137),
+    ("KEY_HELP", 138),
+    ("KEY_MENU", 139),
+    ("KEY_CALC", 140),
+    ("KEY_SETUP", 141),
+    ("KEY_SLEEP", 142),
+    ("KEY_WAKEUP", 143),
+    ("KEY_FILE", 144),
+    ("KEY_SENDFILE", 145),
+    ("KEY_DELETEFILE", 146),
+    ("KEY_XFER", 147),
+    ("KEY_PROG1", 148),
+    ("KEY_PROG2", 149),
+    ("KEY_WWW", 150),
+    ("KEY_MSDOS", 151),
+    ("KEY_COFFEE", 152),
+    ("KEY_SCREENLOCK", 152),
+    ("KEY_DIRECTION", 153),
+    ("KEY_ROTATE_DISPLAY", 153),
+    ("KEY_CYCLEWINDOWS", 154),
+    ("KEY_MAIL", 155),
+    ("KEY_BOOKMARKS", 156),
+    ("KEY_COMPUTER", 157),
+    ("KEY_BACK", 158),
+    ("KEY_FORWARD", 159),
+    ("KEY_CLOSECD", 160),
+    ("KEY_EJECTCD", 161),
+    ("KEY_EJECTCLOSECD", 162),
+    ("KEY_NEXTSONG", 163),
+    ("KEY_PLAYPAUSE", 164),
+    ("KEY_PREVIOUSSONG", 165),
+    ("KEY_STOPCD", 166),
+    ("KEY_RECORD", 167),
+    ("KEY_REWIND", 168),
+    ("KEY_PHONE", 169),
+    ("KEY_ISO", 170),
+    ("KEY_CONFIG", 171),
+    ("KEY_HOMEPAGE", 172),
+    ("KEY_REFRESH", 173),
+    ("KEY_EXIT", 174),
+    ("KEY_MOVE", 175),
+    ("KEY_EDIT", 176),
+    ("KEY_SCROLLUP", 177),
+    ("KEY_SCROLLDOWN", 178),
+    ("KEY_KPLEFTPAREN", 179),
+    ("KEY_KPRIGHTPAREN", 180),
+    ("KEY_NEW", 181),
+    ("KEY_REDO", 182),
+    ("KEY_F13", 183),
+    ("KEY_F14", 184),
+    ("KEY_F15", 185),
+    ("KEY_F16", 186),
+    ("KEY_F17", 187),
+    ("KEY_F18", 188),
+    ("KEY_F19", 189),
+    ("KEY_F20", 190),
+    ("KEY_F21", 191),
+    ("KEY_F22", 192),
+    ("KEY_F23", 193),
+    ("KEY_F24", 194),
+    ("KEY_PLAYCD", 200),
+    ("KEY_PAUSECD", 201),
+    ("KEY_PROG3", 202),
+    ("KEY_PROG4", 203),
+    ("KEY_DASHBOARD", 204),
+    ("KEY_SUSPEND", 205),
+    ("KEY_CLOSE", 206),
+    ("KEY_PLAY", 207),
+    ("KEY_FASTFORWARD", 208),
+    ("KEY_BASSBOOST", 209),
+    ("KEY_PRINT", 210),
+    ("KEY_HP", 211),
+    ("KEY_CAMERA", 212),
+    ("KEY_SOUND", 213),
+    ("KEY_QUESTION", 214),
+    ("KEY_EMAIL", 215),
+    ("KEY_CHAT", 216),
+    ("KEY_SEARCH", 217),
+    ("KEY_CONNECT", 218),
+    ("KEY_FINANCE", 219),
+    ("KEY_SPORT", 220),
+    ("KEY_SHOP", 221),
+    ("KEY_ALTERASE", 222),
+    ("KEY_CANCEL", 223),
+    ("KEY_BRIGHTNESSDOWN", 224),
+    ("KEY_BRIGHTNESSUP", 225),
+    ("KEY_MEDIA", 226),
+    ("KEY_SWITCHVIDEOMODE", 227),
+    ("KEY_KBDILLUMTOGGLE", 228),
+    ("KEY_KBDILLUMDOWN", 229),
+    ("KEY_KBDILLUMUP", 230),
+    ("KEY_SEND", 231),
+    ("KEY_REPLY", 232),
+    ("KEY_FORWARDMAIL", 233),
+    ("KEY_SAVE", 234),
+    ("KEY_DOCUMENTS", 235),
+    ("KEY_BATTERY", 236),
+    ("KEY_BLUETOOTH", 237),
+    ("KEY_WLAN", 238),
+    ("KEY_UWB", 239),
+    ("KEY_UNKNOWN", 240),
+    ("KEY_VIDEO_NEXT", 241),
+    ("KEY_VIDEO_PREV", 242),
+    ("KEY_BRIGHTNESS_CYCLE", 243),
+    ("KEY_BRIGHTNESS_AUTO", 244),
+    ("KEY_BRIGHTNESS_ZERO", 244),
+    ("KEY_DISPLAY_OFF", 245),
+    ("KEY_WIMAX", 246),
+    ("KEY_WWAN", 246),
+    ("KEY_RFKILL", 247),
+    ("KEY_MICMUTE", 248),
+    ("KEY_OK", 352),
+    ("KEY_SELECT", 353),
+    ("KEY_GOTO", 354),
+    ("KEY_CLEAR", 355),
+    ("KEY_POWER2", 356),
+    ("KEY_OPTION", 357),
+    ("KEY_INFO", 358),
+    ("KEY_TIME", 359),
+    ("KEY_VENDOR", 360),
+    ("KEY_ARCHIVE", 361),
+    ("KEY_PROGRAM", 362),
+    ("KEY_CHANNEL", 363),
+    ("KEY_FAVORITES", 364),
+    ("KEY_EPG", 365),
+    ("KEY_PVR", 366),
+    ("KEY_MHP", 367),
+    ("KEY_LANGUAGE", 368),
+    ("KEY_TITLE", 369),
+    ("KEY_SUBTITLE", 370),
+    ("KEY_ANGLE", 371),
+    ("KEY_ZOOM", 372),
+    ("KEY_MODE", 373),
+    ("KEY_KEYBOARD", 374),
+    ("KEY_SCREEN", 375),
+    ("KEY_PC", 376),
+    ("KEY_TV", 377),
+    ("KEY_TV2", 378),
+    ("KEY_VCR", 379),
+    ("KEY_VCR2", 380),
+    ("KEY_SAT", 381),
+    ("KEY_SAT2", 382),
+    ("KEY_CD", 383),
+    ("KEY_TAPE", 384),
+    ("KEY_RADIO", 385),
+    ("KEY_TUNER", 386),
+    ("KEY_PLAYER", 387),
+    ("KEY_TEXT", 388),
+    ("KEY_DVD", 389),
+    ("KEY_AUX", 390),
+    ("KEY_MP3", 391),
+    ("KEY_AUDIO", 392),
+    ("KEY_VIDEO", 393),
+    ("KEY_DIRECTORY", 394),
+    ("KEY_LIST", 395),
+    ("KEY_MEMO", 396),
+    ("KEY_CALENDAR", 397),
+    ("KEY_RED", 398),
+    ("KEY_GREEN", 399),
+    ("KEY_YELLOW", 400),
+    ("KEY_BLUE", 401),
+    ("KEY_CHANNELUP", 402),
+    ("KEY_CHANNELDOWN", 403),
+    ("KEY_FIRST", 404),
+    ("KEY_LAST", 405),
+    ("KEY_AB", 406),
+    ("KEY_NEXT", 407),
+    ("KEY_RESTART", 408),
+    ("KEY_SLOW", 409),
+    ("KEY_SHUFFLE", 410),
+    ("KEY_BREAK", 411),
+    ("KEY_PREVIOUS", 412),
+    ("KEY_DIGITS", 413),
+    ("KEY_TEEN", 414),
+    ("KEY_TWEN", 415),
+    ("KEY_VIDEOPHONE", 416),
+    ("KEY_GAMES", 417),
+    ("KEY_ZOOMIN", 418),
+    ("KEY_ZOOMOUT", 419),
+    ("KEY_ZOOMRESET", 420),
+    ("KEY_WORDPROCESSOR", 421),
+    ("KEY_EDITOR", 422),
+    ("KEY_SPREADSHEET", 423),
+    ("KEY_GRAPHICSEDITOR", 424),
+    ("KEY_PRESENTATION", 425),
+    ("KEY_DATABASE", 426),
+    ("KEY_NEWS", 427),
+    ("KEY_VOICEMAIL", 428),
+    ("KEY_ADDRESSBOOK", 429),
+    ("KEY_MESSENGER", 430),
+    ("KEY_BRIGHTNESS_TOGGLE", 431),
+    ("KEY_DISPLAYTOGGLE", 431),
+    ("KEY_SPELLCHECK", 432),
+    ("KEY_LOGOFF", 433),
+    ("KEY_DOLLAR", 434),
+    ("KEY_EURO", 435),
+    ("KEY_FRAMEBACK", 436),
+    ("KEY_FRAMEFORWARD", 437),
+    ("KEY_CONTEXT_MENU", 438),
+    ("KEY_MEDIA_REPEAT", 439),
+    ("KEY_10CHANNELSUP", 440),
+    ("KEY_10CHANNELSDOWN", 441),
+    ("KEY_IMAGES", 442),
+    ("KEY_DEL_EOL", 448),
+    ("KEY_DEL_EOS", 449),
+    ("KEY_INS_LINE", 450),
+    ("KEY_DEL_LINE", 451),
+    ("KEY_FN", 464),
+    ("KEY_FN_ESC", 465),
+    ("KEY_FN_F1", 466),
+    ("KEY_FN_F2", 467),
+    ("KEY_FN_F3", 468),
+    ("KEY_FN_F4", 469),
+    ("KEY_FN_F5", 470),
+    ("KEY_FN_F6", 471),
+    ("KEY_FN_F7", 472),
+    ("KEY_FN_F8", 473),
+    ("KEY_FN_F9", 474),
+    ("KEY_FN_F10", 475),
+    ("KEY_FN_F11", 476),
+    ("KEY_FN_F12", 477),
+    ("KEY_FN_1", 478),
+    ("KEY_FN_2", 479),
+    ("KEY_FN_D", 480),
+    ("KEY_FN_E", 481),
+    ("KEY_FN_F", 482),
+    ("KEY_FN_S", 483),
+    ("KEY_FN_B", 484),
+    ("KEY_BRL_DOT1", 497),
+    ("KEY_BRL_DOT2", 498),
+    ("KEY_BRL_DOT3", 499),
+    ("KEY_BRL_DOT4", 500),
+    ("KEY_BRL_DOT5", 501),
+    ("KEY_BRL_DOT6", 502),
+    ("KEY_BRL_DOT7", 503),
+    ("KEY_BRL_DOT8", 504),
+    ("KEY_BRL_DOT9", 505),
+    ("KEY_BRL_DOT10", 506),
+    ("KEY_NUMERIC_0", 512),
+    ("KEY_NUMERIC_1", 513),
+    ("KEY_NUMERIC_2", 514),
+    ("KEY_NUMERIC_3", 515),
+    ("KEY_NUMERIC_4", 516),
+    ("KEY_NUMERIC_5", 517),
+    ("KEY_NUMERIC_6", 518),
+    ("KEY_NUMERIC_7", 519),
+    ("KEY_NUMERIC_8", 520),
+    ("KEY_NUMERIC_9", 521),
+    ("KEY_NUMERIC_STAR", 522),
+    ("KEY_NUMERIC_POUND", 523),
+    ("KEY_NUMERIC_A", 524),
+    ("KEY_NUMERIC_B", 525),
+    ("KEY_NUMERIC_C", 526),
+    ("KEY_NUMERIC_D", 527),
+    ("KEY_CAMERA_FOCUS", 528),
+    ("KEY_WPS_BUTTON", 529),
+    ("KEY_TOUCHPAD_TOGGLE", 530),
+    ("KEY_TOUCHPAD_ON", 531),
+    ("KEY_TOUCHPAD_OFF", 532),
+    ("KEY_CAMERA_ZOOMIN", 533),
+    ("KEY_CAMERA_ZOOMOUT", 534),
+    ("KEY_CAMERA_UP", 535),
+    ("KEY_CAMERA_DOWN", 536),
+    ("KEY_CAMERA_LEFT", 537),
+    ("KEY_CAMERA_RIGHT", 538),
+    ("KEY_ATTENDANT_ON", 539),
+    ("KEY_ATTENDANT_OFF", 540),
+    ("KEY_ATTENDANT_TOGGLE", 541),
+    ("KEY_LIGHTS_TOGGLE", 542),
+    ("KEY_ALS_TOGGLE", 560),
+    ("KEY_BUTTONCONFIG", 576),
+    ("KEY_TASKMANAGER", 577),
+    ("KEY_JOURNAL", 578),
+    ("KEY_CONTROLPANEL", 579),
+    ("KEY_APPSELECT", 580),
+    ("KEY_SCREENSAVER", 581),
+    ("KEY_VOICECOMMAND", 582),
+    ("KEY_BRIGHTNESS_MIN", 592),
+    ("KEY_BRIGHTNESS_MAX", 593),
+    ("KEY_KBDINPUTASSIST_PREV", 608),
+    ("KEY_KBDINPUTASSIST_NEXT", 609),
+    ("KEY_KBDINPUTASSIST_PREVGROUP", 610),
+    ("KEY_KBDINPUTASSIST_NEXTGROUP", 611),
+    ("KEY_KBDINPUTASSIST_ACCEPT", 612),
+    ("KEY_KBDINPUTASSIST_CANCEL", 613),
+];
+
+/// X keysym names, with the kernel key that types them on a US layout and
+/// whether it needs shift.
+pub const KEYSYMS: &[(&str, &str, bool)] = &[
+    ("a", "KEY_A", false),
+    ("b", "KEY_B", false),
+    ("c", "KEY_C", false),
+    ("d", "KEY_D", false),
+    ("e", "KEY_E", false),
+    ("f", "KEY_F", false),
+    ("g", "KEY_G", false),
+    ("h", "KEY_H", false),
+    ("i", "KEY_I", false),
+    ("j", "KEY_J", false),
+    ("k", "KEY_K", false),
+    ("l", "KEY_L", false),
+    ("m", "KEY_M", false),
+    ("n", "KEY_N", false),
+    ("o", "KEY_O", false),
+    ("p", "KEY_P", false),
+    ("q", "KEY_Q", false),
+    ("r", "KEY_R", false),
+    ("s", "KEY_S", false),
+    ("t", "KEY_T", false),
+    ("u", "KEY_U", false),
+    ("v", "KEY_V", false),
+    ("w", "KEY_W", false),
+    ("x", "KEY_X", false),
+    ("y", "KEY_Y", false),
+    ("z", "KEY_Z", false),
+    ("A", "KEY_A", true),
+    ("B", "KEY_B", true),
+    ("C", "KEY_C", true),
+    ("D", "KEY_D", true),
+    ("E", "KEY_E", true),
+    ("F", "KEY_F", true),
+    ("G", "KEY_G", true),
+    ("H", "KEY_H", true),
+    ("I", "KEY_I", true),
+    ("J", "KEY_J", true),
+    ("K", "KEY_K", true),
+    ("L", "KEY_L", true),
+    ("M", "KEY_M", true),
+    ("N", "KEY_N", true),
+    ("O", "KEY_O", true),
+    ("P", "KEY_P", true),
+    ("Q", "KEY_Q", true),
+    ("R", "KEY_R", true),
+    ("S", "KEY_S", true),
+    ("T", "KEY_T", true),
+    ("U", "KEY_U", true),
+    ("V", "KEY_V", true),
+    ("W", "KEY_W", true),
+    ("X", "KEY_X", true),
+    ("Y", "KEY_Y", true),
+    ("Z", "KEY_Z", true),
+    ("0", "KEY_0", false),
+    ("1", "KEY_1", false),
+    ("2", "KEY_2", false),
+    ("3", "KEY_3", false),
+    ("4", "KEY_4", false),
+    ("5", "KEY_5", false),
+    ("6", "KEY_6", false),
+    ("7", "KEY_7", false),
+    ("8", "KEY_8", false),
+    ("9", "KEY_9", false),
+    ("exclam", "KEY_1", true),
+    ("at", "KEY_2", true),
+    ("numbersign", "KEY_3", true),
+    ("dollar", "KEY_4", true),
+    ("percent", "KEY_5", true),
+    ("asciicircum", "KEY_6", true),
+    ("ampersand", "KEY_7", true),
+    ("asterisk", "KEY_8", true),
+    ("parenleft", "KEY_9", true),
+    ("parenright", "KEY_0", true),
+    ("space", "KEY_SPACE", false),
+    ("minus", "KEY_MINUS", false),
+    ("underscore", "KEY_MINUS", true),
+    ("equal", "KEY_EQUAL", false),
+    ("plus", "KEY_EQUAL", true),
+    ("bracketleft", "KEY_LEFTBRACE", false),
+    ("braceleft", "KEY_LEFTBRACE", true),
+    ("bracketright", "KEY_RIGHTBRACE", false),
+    ("braceright", "KEY_RIGHTBRACE", true),
+    ("backslash", "KEY_BACKSLASH", false),
+    ("bar", "KEY_BACKSLASH", true),
+    ("semicolon", "KEY_SEMICOLON", false),
+    ("colon", "KEY_SEMICOLON", true),
+    ("apostrophe", "KEY_APOSTROPHE", false),
+    ("quotedbl", "KEY_APOSTROPHE", true),
+    ("grave", "KEY_GRAVE", false),
+    ("asciitilde", "KEY_GRAVE", true),
+    ("comma", "KEY_COMMA", false),
+    ("less", "KEY_COMMA", true),
+    ("period", "KEY_DOT", false),
+    ("greater", "KEY_DOT", true),
+    ("slash", "KEY_SLASH", false),
+    ("question", "KEY_SLASH", true),
+    ("Escape", "KEY_ESC", false),
+    ("Return", "KEY_ENTER", false),
+    ("Tab", "KEY_TAB", false),
+    ("BackSpace", "KEY_BACKSPACE", false),
+    ("Delete", "KEY_DELETE", false),
+    ("Insert", "KEY_INSERT", false),
+    ("Home", "KEY_HOME", false),
+    ("End", "KEY_END", false),
+    ("Prior", "KEY_PAGEUP", false),
+    ("Page_Up", "KEY_PAGEUP", false),
+    ("Next", "KEY_PAGEDOWN", false),
+    ("Page_Down", "KEY_PAGEDOWN", false),
+    ("Left", "KEY_LEFT", false),
+    ("Right", "KEY_RIGHT", false),
+    ("Up", "KEY_UP", false),
+    ("Down", "KEY_DOWN", false),
+    ("F1", "KEY_F1", false),
+    ("F2", "KEY_F2", false),
+    ("F3", "KEY_F3", false),
+    ("F4", "KEY_F4", false),
+    ("F5", "KEY_F5", false),
+    ("F6", "KEY_F6", false),
+    ("F7", "KEY_F7", false),
+    ("F8", "KEY_F8", false),
+    ("F9", "KEY_F9", false),
+    ("F10", "KEY_F10", false),
+    ("F11", "KEY_F11", false),
+    ("F12", "KEY_F12", false),
+    ("F13", "KEY_F13", false),
+    ("F14", "KEY_F14", false),
+    ("F15", "KEY_F15", false),
+    ("F16", "KEY_F16", false),
+    ("F17", "KEY_F17", false),
+    ("F18", "KEY_F18", false),
+    ("F19", "KEY_F19", false),
+    ("F20", "KEY_F20", false),
+    ("F21", "KEY_F21", false),
+    ("F22", "KEY_F22", false),
+    ("F23", "KEY_F23", false),
+    ("F24", "KEY_F24", false),
+    ("Caps_Lock", "KEY_CAPSLOCK", false),
+    ("Num_Lock", "KEY_NUMLOCK", false),
+    ("Scroll_Lock", "KEY_SCROLLLOCK", false),
+    ("Print", "KEY_SYSRQ", false),
+    ("Pause", "KEY_PAUSE", false),
+    ("Menu", "KEY_COMPOSE", false),
+    ("Linefeed", "KEY_LINEFEED", false),
+    ("Shift_L", "KEY_LEFTSHIFT", false),
+    ("Shift_R", "KEY_RIGHTSHIFT", false),
+    ("Control_L", "KEY_LEFTCTRL", false),
+    ("Control_R", "KEY_RIGHTCTRL", false),
+    ("Alt_L", "KEY_LEFTALT", false),
+    ("Alt_R", "KEY_RIGHTALT", false),
+    ("Super_L", "KEY_LEFTMETA", false),
+    ("Super_R", "KEY_RIGHTMETA", false),
+    ("ISO_Level3_Shift", "KEY_RIGHTALT", false),
+    ("KP_0", "KEY_KP0", false),
+    ("KP_1", "KEY_KP1", false),
+    ("KP_2", "KEY_KP2", false),
+    ("KP_3", "KEY_KP3", false),
+    ("KP_4", "KEY_KP4", false),
+    ("KP_5", "KEY_KP5", false),
+    ("KP_6", "KEY_KP6", false),
+    ("KP_7", "KEY_KP7", false),
+    ("KP_8", "KEY_KP8", false),
+    ("KP_9", "KEY_KP9", false),
+    ("KP_Decimal", "KEY_KPDOT", false),
+    ("KP_Add", "KEY_KPPLUS", false),
+    ("KP_Subtract", "KEY_KPMINUS", false),
+    ("KP_Multiply", "KEY_KPASTERISK", false),
+    ("KP_Divide", "KEY_KPSLASH", false),
+    ("KP_Enter", "KEY_KPENTER", false),
+    ("KP_Equal", "KEY_KPEQUAL", false),
+    ("XF86AudioMute", "KEY_MUTE", false),
+    ("XF86AudioLowerVolume", "KEY_VOLUMEDOWN", false),
+    ("XF86AudioRaiseVolume", "KEY_VOLUMEUP", false),
+    ("XF86AudioMicMute", "KEY_MICMUTE", false),
+    ("XF86AudioPlay", "KEY_PLAYPAUSE", false),
+    ("XF86AudioStop", "KEY_STOPCD", false),
+    ("XF86AudioPrev", "KEY_PREVIOUSSONG", false),
+    ("XF86AudioNext", "KEY_NEXTSONG", false),
+    ("XF86MonBrightnessUp", "KEY_BRIGHTNESSUP", false),
+    ("XF86MonBrightnessDown", "KEY_BRIGHTNESSDOWN", false),
+    ("XF86PowerOff", "KEY_POWER", false),
+    ("XF86Sleep", "KEY_SLEEP", false),
+    ("XF86Calculator", "KEY_CALC", false),
+    ("XF86Mail", "KEY_MAIL", false),
+    ("XF86HomePage", "KEY_HOMEPAGE", false),
+    ("XF86Search", "KEY_SEARCH", false),
+    ("XF86Back", "KEY_BACK", false),
+    ("XF86Forward", "KEY_FORWARD", false),
+    ("XF86Reload", "KEY_REFRESH", false),
+    ("XF86Copy", "KEY_COPY", false),
+    ("XF86Paste", "KEY_PASTE", false),
+    ("XF86Cut", "KEY_CUT", false),
+    ("XF86Eject", "KEY_EJECTCD", false),
+];
+
+/// Printable characters whose keysym isn't the character itself.
+const CHARACTERS: &[(char, &str)] = &[
+    (' ', "space"),
+    ('!', "exclam"),
+    ('@', "at"),
+    ('#', "numbersign"),
+    ('$', "dollar"),
+    ('%', "percent"),
+    ('^', "asciicircum"),
+    ('&', "ampersand"),
+    ('*', "asterisk"),
+    ('(', "parenleft"),
+    (')', "parenright"),
+    ('-', "minus"),
+    ('_', "underscore"),
+    ('=', "equal"),
+    ('+', "plus"),
+    ('[', "bracketleft"),
+    ('{', "braceleft"),
+    (']', "bracketright"),
+    ('}', "braceright"),
+    ('\\', "backslash"),
+    ('|', "bar"),
+    (';', "semicolon"),
+    (':', "colon"),
+    ('\'', "apostrophe"),
+    ('"', "quotedbl"),
+    ('`', "grave"),
+    ('~', "asciitilde"),
+    (',', "comma"),
+    ('<', "less"),
+    ('.', "period"),
+    ('>', "greater"),
+    ('/', "slash"),
+    ('?', "question"),
+];
+
+/// The X keysym name for `name`, translating characters like `!` to their
+/// keysym (`exclam`) and leaving anything else as it is.
+pub fn keysym_name(name: &str) -> &str {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => CHARACTERS
+            .iter()
+            .find(|(character, _)| *character == c)
+            .map_or(name, |(_, keysym)| *keysym),
+        _ => name,
+    }
+}
+
+fn evdev_code(name: &str) -> Option<u16> {
+    EVDEV_KEYS
+        .iter()
+        .find(|(evdev_name, _)| evdev_name.eq_ignore_ascii_case(name))
+        .map(|(_, code)| *code)
+}
+
+/// Look up a key by kernel name, X keysym name or character.
+pub fn lookup(name: &str) -> Option<KeyCode> {
+    if let Some(code) = evdev_code(name) {
+        return Some(KeyCode { code, shift: false });
+    }
+    let keysym = keysym_name(name);
+    KEYSYMS
+        .iter()
+        .find(|(keysym_name, _, _)| *keysym_name == keysym)
+        .and_then(|(_, evdev_name, shift)| {
+            evdev_code(evdev_name).map(|code| KeyCode {
+                code,
+                shift: *shift,
+            })
+        })
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Up to three known key names that are spelled most like `name`, to
+/// suggest when it isn't a key.
+pub fn suggest(name: &str) -> Vec<String> {
+    // Compare without case or the kernel's KEY_ prefix, so `enter` finds
+    // KEY_ENTER and `escape` finds Escape
+    let fold = |name: &str| {
+        let lower = name.to_ascii_lowercase();
+        lower.strip_prefix("key_").unwrap_or(&lower).to_owned()
+    };
+    let folded = fold(name);
+    let max_distance = (folded.chars().count() / 3).max(2);
+    let mut candidates: Vec<(usize, &str)> = EVDEV_KEYS
+        .iter()
+        .map(|(evdev_name, _)| *evdev_name)
+        .chain(KEYSYMS.iter().map(|(keysym, _, _)| *keysym))
+        .filter(|candidate| candidate.chars().count() > 1)
+        .map(|candidate| (edit_distance(&folded, &fold(candidate)), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+    candidates
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate.to_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: u16, shift: bool) -> Option<KeyCode> {
+        Some(KeyCode { code, shift })
+    }
+
+    #[test]
+    fn keys_resolve_by_any_name() {
+        assert_eq!(lookup("KEY_F5"), key(63, false));
+        assert_eq!(lookup("key_f5"), key(63, false));
+        assert_eq!(lookup("F5"), key(63, false));
+        assert_eq!(lookup("Return"), lookup("KEY_ENTER"));
+        assert_eq!(lookup("Left"), key(105, false));
+        assert_eq!(lookup("a"), key(30, false));
+        assert_eq!(lookup("A"), key(30, true));
+        assert_eq!(lookup("!"), lookup("exclam"));
+        assert_eq!(lookup("!"), key(2, true));
+        assert_eq!(lookup(" "), lookup("space"));
+        assert_eq!(lookup("KP_Enter"), lookup("KEY_KPENTER"));
+        assert_eq!(lookup("XF86AudioMute"), lookup("KEY_MUTE"));
+        assert_eq!(lookup("KEY_BRIGHTNESS_MAX"), key(0x251, false));
+    }
+
+    #[test]
+    fn unknown_keys_are_not_found() {
+        assert_eq!(lookup("KEY_NOPE"), None);
+        assert_eq!(lookup("return"), None);
+        assert_eq!(lookup("é"), None);
+    }
+
+    #[test]
+    fn misspelled_keys_get_suggestions() {
+        assert_eq!(suggest("Retrun")[0], "Return");
+        assert_eq!(suggest("enter")[0], "KEY_ENTER");
+        assert_eq!(suggest("KEY_ESCAPE")[0], "Escape");
+        assert!(suggest("completely-wrong").is_empty());
+    }
+
+    #[test]
+    fn every_keysym_maps_to_a_kernel_key() {
+        for (keysym, evdev_name, _) in KEYSYMS {
+            assert!(
+                evdev_code(evdev_name).is_some(),
+                "{} -> {}",
+                keysym,
+                evdev_name
+            );
+        }
+    }
+}
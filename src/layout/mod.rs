@@ -0,0 +1,660 @@
+//! Keyboard layouts, read from the XKB data files X11 and wayland
+//! compositors use, for backends that send kernel key codes and so have to
+//! know which key, and which shift level of it, types a character.
+//!
+//! Only the parts of the `keycodes` and `symbols` files that say which
+//! keysyms are on which key are read. Layouts are loaded as the `pc` symbols
+//! followed by the layout's own, as the `evdev` rules combine them, and only
+//! the first group of each key is used.
+
+mod keysyms;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use log::debug;
+
+use crate::chord::Modifier;
+use crate::errors::Error;
+
+/// Where the XKB data files are unless `XKB_CONFIG_ROOT` says otherwise.
+pub const DEFAULT_XKB_ROOT: &str = "/usr/share/X11/xkb";
+
+/// The layout used when the system doesn't say which one it has.
+pub const DEFAULT_LAYOUT: &str = "us";
+
+/// How deep includes may nest before a layout is taken to include itself.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// The character a keysym name types, e.g. `adiaeresis` for `ä`, `U20AC`
+/// or `0x10020ac` for `€`.
+pub fn keysym_char(name: &str) -> Option<char> {
+    let is_hex = |hex: &&str| hex.len() >= 4 && hex.chars().all(|c| c.is_ascii_hexdigit());
+    let code_point = if let Some(hex) = name.strip_prefix('U').filter(is_hex) {
+        u32::from_str_radix(hex, 16).ok()?
+    } else if let Some(hex) = name.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16)
+            .ok()?
+            .checked_sub(0x0100_0000)?
+    } else {
+        keysyms::KEYSYM_CHARS
+            .iter()
+            .find(|(keysym, _)| *keysym == name)
+            .map(|(_, code_point)| *code_point)?
+    };
+    char::from_u32(code_point)
+}
+
+/// The key that types a character on a layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutKey {
+    /// The kernel key code.
+    pub code: u16,
+    /// The shift level, from 0 for the key on its own to 3 for AltGr+Shift.
+    pub level: u8,
+}
+
+impl LayoutKey {
+    /// The modifiers to hold down to reach the key's level.
+    pub fn modifiers(&self) -> Vec<Modifier> {
+        Modifier::for_level(self.level)
+    }
+}
+
+/// A keyboard layout, e.g. `de` or `fr(bepo)`, as the characters on its keys.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    name: String,
+    keys: HashMap<char, LayoutKey>,
+}
+
+impl Layout {
+    /// Load the layout named `LAYOUT` or `LAYOUT(VARIANT)` from the XKB data
+    /// files.
+    pub fn load(name: &str) -> Result<Self> {
+        let root = std::env::var_os("XKB_CONFIG_ROOT")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_XKB_ROOT));
+        debug!("Loading keyboard layout {} from {:?}", name, root);
+        Self::from_files(name, |path| std::fs::read_to_string(root.join(path)).ok())
+    }
+
+    /// The name of the layout the system is set up with: the first layout in
+    /// `XKB_DEFAULT_LAYOUT`, or in `XKBLAYOUT` in `/etc/default/keyboard`,
+    /// with its variant, or [`DEFAULT_LAYOUT`] if neither is set.
+    pub fn system_default() -> String {
+        let from_env = std::env::var("XKB_DEFAULT_LAYOUT").ok().map(|layout| {
+            (
+                layout,
+                std::env::var("XKB_DEFAULT_VARIANT").unwrap_or_default(),
+            )
+        });
+        let from_file = || {
+            let settings = std::fs::read_to_string("/etc/default/keyboard").ok()?;
+            let setting = |name: &str| {
+                settings.lines().find_map(|line| {
+                    let value = line.trim().strip_prefix(name)?.strip_prefix('=')?;
+                    Some(value.trim_matches(|c| c == '"' || c == '\'').to_owned())
+                })
+            };
+            Some((
+                setting("XKBLAYOUT")?,
+                setting("XKBVARIANT").unwrap_or_default(),
+            ))
+        };
+        match from_env.or_else(from_file) {
+            Some((layouts, variants)) => {
+                let layout = layouts.split(',').next().unwrap_or_default().trim();
+                let variant = variants.split(',').next().unwrap_or_default().trim();
+                match (layout, variant) {
+                    ("", _) => DEFAULT_LAYOUT.to_owned(),
+                    (layout, "") => layout.to_owned(),
+                    (layout, variant) => format!("{}({})", layout, variant),
+                }
+            }
+            None => DEFAULT_LAYOUT.to_owned(),
+        }
+    }
+
+    /// Build the layout named `name` from files read by `read`, which is
+    /// given paths relative to the XKB data root, e.g. `symbols/de`.
+    fn from_files<F: Fn(&str) -> Option<String>>(name: &str, read: F) -> Result<Self> {
+        let fail = |reason: String| Error::KeyboardLayout(name.to_owned(), reason);
+        let (file, section) = split_section(name);
+        if file.is_empty() || file.contains('/') || file.starts_with('.') {
+            return Err(fail("expected LAYOUT or LAYOUT(VARIANT)".to_owned()).into());
+        }
+
+        let mut loader = Loader {
+            read: &read,
+            keycodes: HashMap::new(),
+            aliases: HashMap::new(),
+            symbols: HashMap::new(),
+        };
+        loader
+            .include("keycodes", "evdev", None, Merge::Override, 0)
+            .and_then(|_| loader.include("symbols", "pc", None, Merge::Override, 0))
+            .and_then(|_| loader.include("symbols", file, section, Merge::Override, 0))
+            .map_err(fail)?;
+
+        // Prefer the lowest level a character is on, then the lowest key code
+        let mut keys: HashMap<char, LayoutKey> = HashMap::new();
+        for (key_name, levels) in &loader.symbols {
+            let code = match loader.kernel_code(key_name) {
+                Some(code) => code,
+                None => continue,
+            };
+            for (level, keysym) in levels.iter().enumerate().take(4) {
+                let c = match keysym.as_deref().and_then(keysym_char) {
+                    Some(c) => c,
+                    None => continue,
+                };
+                let key = LayoutKey {
+                    code,
+                    level: level as u8,
+                };
+                let better = keys
+                    .get(&c)
+                    .is_none_or(|known| (key.level, key.code) < (known.level, known.code));
+                if better {
+                    keys.insert(c, key);
+                }
+            }
+        }
+        if keys.is_empty() {
+            return Err(fail("it has no keys".to_owned()).into());
+        }
+        Ok(Layout {
+            name: name.to_owned(),
+            keys,
+        })
+    }
+
+    /// The layout's name, as it was loaded.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The key that types `c`, if it's on the layout.
+    pub fn lookup(&self, c: char) -> Option<LayoutKey> {
+        self.keys.get(&c).copied()
+    }
+}
+
+impl std::fmt::Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Split `file(section)` into the file and section names.
+fn split_section(name: &str) -> (&str, Option<&str>) {
+    match name.strip_suffix(')').and_then(|name| name.split_once('(')) {
+        Some((file, section)) => (file.trim(), Some(section.trim())),
+        None => (name.trim(), None),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Str(String),
+    KeyName(String),
+    Punct(char),
+}
+
+/// Split an XKB file into tokens, dropping comments.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            _ if c.is_whitespace() => {}
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '#' => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            '"' => tokens.push(Token::Str(
+                chars.by_ref().take_while(|&c| c != '"').collect(),
+            )),
+            '<' => tokens.push(Token::KeyName(
+                chars.by_ref().take_while(|&c| c != '>').collect(),
+            )),
+            _ if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            _ => tokens.push(Token::Punct(c)),
+        }
+    }
+    tokens
+}
+
+/// A section of an XKB file, e.g. `xkb_symbols "basic" { ... };`.
+struct Section {
+    name: String,
+    default: bool,
+    body: Vec<Token>,
+}
+
+/// Split a file into its sections.
+fn sections(tokens: &[Token]) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut default = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Word(word) if word == "default" => default = true,
+            Token::Word(word) if word.starts_with("xkb_") => {
+                let name = match tokens.get(i + 1) {
+                    Some(Token::Str(name)) => name.clone(),
+                    _ => String::new(),
+                };
+                let open = match tokens[i..].iter().position(|t| *t == Token::Punct('{')) {
+                    Some(open) => i + open,
+                    None => break,
+                };
+                let close = matching(tokens, open);
+                sections.push(Section {
+                    name,
+                    default,
+                    body: tokens[open + 1..close].to_vec(),
+                });
+                default = false;
+                i = close;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    sections
+}
+
+/// The index of the bracket closing the one at `open`, or the end of
+/// `tokens` if it's never closed.
+fn matching(tokens: &[Token], open: usize) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Punct('{') | Token::Punct('[') | Token::Punct('(') => depth += 1,
+            Token::Punct('}') | Token::Punct(']') | Token::Punct(')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// Split a section's body into statements, with includes as statements of
+/// their own since they don't end with a semicolon.
+fn statements(body: &[Token]) -> Vec<&[Token]> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < body.len() {
+        match (&body[i], body.get(i + 1)) {
+            (Token::Word(word), Some(Token::Str(_)))
+                if i == start
+                    && ["include", "augment", "override", "replace"].contains(&&**word) =>
+            {
+                statements.push(&body[i..i + 2]);
+                i += 2;
+                start = i;
+                continue;
+            }
+            (Token::Punct('{'), _) | (Token::Punct('['), _) | (Token::Punct('('), _) => {
+                i = matching(body, i);
+            }
+            (Token::Punct(';'), _) => {
+                statements.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if start < body.len() {
+        statements.push(&body[start..]);
+    }
+    statements
+}
+
+/// How a definition combines with one already made for the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Merge {
+    /// Replace the levels it defines.
+    Override,
+    /// Only fill in levels that aren't defined yet.
+    Augment,
+}
+
+impl Merge {
+    fn within(self, outer: Merge) -> Merge {
+        match outer {
+            Merge::Augment => Merge::Augment,
+            Merge::Override => self,
+        }
+    }
+}
+
+struct Loader<'a, F: Fn(&str) -> Option<String>> {
+    read: &'a F,
+    /// XKB key codes by key name.
+    keycodes: HashMap<String, u32>,
+    /// Key names by alias.
+    aliases: HashMap<String, String>,
+    /// The keysym names on each level of the first group of each key.
+    symbols: HashMap<String, Vec<Option<String>>>,
+}
+
+impl<'a, F: Fn(&str) -> Option<String>> Loader<'a, F> {
+    /// Read the section of `kind/file` named `section`, or its default
+    /// section.
+    fn include(
+        &mut self,
+        kind: &str,
+        file: &str,
+        section: Option<&str>,
+        merge: Merge,
+        depth: usize,
+    ) -> std::result::Result<(), String> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(format!(
+                "includes nest more than {} deep",
+                MAX_INCLUDE_DEPTH
+            ));
+        }
+        let path = format!("{}/{}", kind, file);
+        let text = (self.read)(&path).ok_or_else(|| format!("{} could not be read", path))?;
+        let sections = sections(&tokenize(&text));
+        let chosen = match section {
+            Some(section) => sections.iter().find(|s| s.name == section),
+            None => sections
+                .iter()
+                .find(|s| s.default)
+                .or_else(|| sections.first()),
+        }
+        .ok_or_else(|| match section {
+            Some(section) => format!("{} has no section {}", path, section),
+            None => format!("{} has no sections", path),
+        })?;
+
+        for statement in statements(&chosen.body) {
+            self.statement(kind, statement, merge, depth)?;
+        }
+        Ok(())
+    }
+
+    fn statement(
+        &mut self,
+        kind: &str,
+        statement: &[Token],
+        merge: Merge,
+        depth: usize,
+    ) -> std::result::Result<(), String> {
+        let (statement_merge, rest) = match statement.first() {
+            Some(Token::Word(word)) if word == "augment" => (Merge::Augment, &statement[1..]),
+            Some(Token::Word(word)) if word == "override" || word == "replace" => {
+                (Merge::Override, &statement[1..])
+            }
+            _ => (Merge::Override, statement),
+        };
+        let merge = statement_merge.within(merge);
+        match rest {
+            [Token::Str(includes)] | [Token::Word(_), Token::Str(includes)] => {
+                // `+` overrides what's been read so far, `|` augments it
+                let mut include_merge = merge;
+                let mut start = 0;
+                for (i, c) in includes.char_indices().chain(Some((includes.len(), '+'))) {
+                    if c == '+' || c == '|' {
+                        let (file, section) = split_section(&includes[start..i]);
+                        if !file.is_empty() {
+                            self.include(kind, file, section, include_merge, depth + 1)?;
+                        }
+                        include_merge = if c == '|' {
+                            Merge::Augment
+                        } else {
+                            Merge::Override
+                        }
+                        .within(merge);
+                        start = i + c.len_utf8();
+                    }
+                }
+            }
+            [Token::KeyName(name), Token::Punct('='), Token::Word(code)] => {
+                if let Ok(code) = code.parse() {
+                    self.keycodes.insert(name.clone(), code);
+                }
+            }
+            [Token::Word(alias), Token::KeyName(name), Token::Punct('='), Token::KeyName(key)]
+                if alias == "alias" =>
+            {
+                self.aliases.insert(name.clone(), key.clone());
+            }
+            [Token::Word(key), Token::KeyName(name), Token::Punct('{'), body @ ..]
+                if key == "key" =>
+            {
+                if let Some(levels) = first_group(body) {
+                    self.merge_key(name, levels, merge);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn merge_key(&mut self, name: &str, levels: Vec<Option<String>>, merge: Merge) {
+        let name = self
+            .aliases
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_owned());
+        let known = self.symbols.entry(name).or_default();
+        if known.len() < levels.len() {
+            known.resize(levels.len(), None);
+        }
+        for (known, keysym) in known.iter_mut().zip(levels) {
+            if keysym.is_some() && (merge == Merge::Override || known.is_none()) {
+                *known = keysym;
+            }
+        }
+    }
+
+    fn kernel_code(&self, name: &str) -> Option<u16> {
+        let name = self.aliases.get(name).map_or(name, |name| name.as_str());
+        // XKB key codes are the kernel's plus 8, as X11 numbers them
+        let code = self.keycodes.get(name)?.checked_sub(8)?;
+        u16::try_from(code).ok().filter(|code| *code > 0)
+    }
+}
+
+/// The keysym names on each level of the first group in the body of a key
+/// statement, written either as a bare `[ ... ]` list or as
+/// `symbols[Group1] = [ ... ]`. Levels with `NoSymbol` are `None`.
+fn first_group(body: &[Token]) -> Option<Vec<Option<String>>> {
+    let list = |start: usize| {
+        let end = matching(body, start);
+        body[start + 1..end.min(body.len())]
+            .split(|token| *token == Token::Punct(','))
+            .map(|keysym| match keysym {
+                [Token::Word(keysym)] if keysym != "NoSymbol" && keysym != "VoidSymbol" => {
+                    Some(keysym.clone())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut item_start = true;
+    let mut i = 0;
+    while i < body.len() {
+        match &body[i..] {
+            [Token::Punct('['), ..] if item_start => return Some(list(i)),
+            [Token::Word(symbols), Token::Punct('['), Token::Word(group), Token::Punct(']'), Token::Punct('='), Token::Punct('['), ..]
+                if symbols == "symbols" =>
+            {
+                let group = group.to_ascii_lowercase();
+                if group == "group1" || group == "1" {
+                    return Some(list(i + 5));
+                }
+                i = matching(body, i + 5);
+            }
+            [Token::Punct('['), ..] | [Token::Punct('{'), ..] => i = matching(body, i),
+            _ => {}
+        }
+        item_start = body.get(i) == Some(&Token::Punct(','));
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYCODES: &str = r#"
+        // Key codes, as in keycodes/evdev
+        default xkb_keycodes "evdev" {
+            minimum = 8;
+            maximum = 255;
+            <AE02> = 11; <AE11> = 20; <AD01> = 24; <AD03> = 26;
+            <AD06> = 29; <AC01> = 38; <AB01> = 52; <SPCE> = 65;
+            <LSGT> = 94; <BKSL> = 51;
+            alias <AC12> = <BKSL>;
+            indicator 1 = "Caps Lock";
+        };
+    "#;
+
+    const PC: &str = r#"
+        default partial alphanumeric_keys modifier_keys
+        xkb_symbols "pc105" {
+            key <SPCE> { [ space ] };
+            key <LSGT> { [ less, greater, bar, brokenbar ] };
+        };
+    "#;
+
+    const LATIN: &str = r#"
+        default partial
+        xkb_symbols "basic" {
+            key <AE02> { [ 2, at, twosuperior, oneeighth ] };
+            key <AD01> { [ q, Q, at, Greek_OMEGA ] };
+            key <AD03> { [ e, E, EuroSign, cent ] };
+            key <AD06> { [ y, Y, leftarrow, yen ] };
+            key <AC01> { [ a, A, ae, AE ] };
+            key <AB01> { [ z, Z, guillemotleft, less ] };
+            key <AC12> { [ backslash, bar ] };
+        };
+
+        partial
+        xkb_symbols "type4" {
+            include "latin"
+            key <AE02> { [ 2, quotedbl, at, oneeighth ] };
+        };
+    "#;
+
+    const DE: &str = r#"
+        default
+        xkb_symbols "basic" {
+            include "latin(type4)"
+
+            name[Group1]="German";
+
+            key <AE02> { [ 2, quotedbl, twosuperior, oneeighth ] };
+            key <AE11> {type[Group1]="FOUR_LEVEL_PLUS_LOCK",  symbols[Group1]=
+                          [ssharp, question, backslash, questiondown, 0x1001E9E ]};
+            key <AD06> { [ z, Z, leftarrow, yen ] };
+            key <AB01> { [ y, Y, NoSymbol, U203A ] };
+            key <BKSL> { [numbersign, apostrophe, rightsinglequotemark, dead_breve ] };
+            /* A dead key types nothing by itself */
+            key <AC01> { [ dead_acute, A ] };
+        };
+
+        partial
+        xkb_symbols "nodeadkeys" {
+            include "de(basic)"
+            augment "latin(basic)"
+            key <AC01> { [ a ] };
+        };
+    "#;
+
+    fn layout(name: &str) -> Result<Layout> {
+        Layout::from_files(name, |path| match path {
+            "keycodes/evdev" => Some(KEYCODES.to_owned()),
+            "symbols/pc" => Some(PC.to_owned()),
+            "symbols/latin" => Some(LATIN.to_owned()),
+            "symbols/de" => Some(DE.to_owned()),
+            _ => None,
+        })
+    }
+
+    fn key(code: u16, level: u8) -> Option<LayoutKey> {
+        Some(LayoutKey { code, level })
+    }
+
+    #[test]
+    fn keysyms_name_characters() {
+        assert_eq!(keysym_char("adiaeresis"), Some('ä'));
+        assert_eq!(keysym_char("EuroSign"), Some('€'));
+        assert_eq!(keysym_char("U2032"), Some('′'));
+        assert_eq!(keysym_char("0x1001E9E"), Some('ẞ'));
+        assert_eq!(keysym_char("2"), Some('2'));
+        assert_eq!(keysym_char("dead_acute"), None);
+        assert_eq!(keysym_char("Return"), None);
+        assert_eq!(keysym_char("U"), Some('U'));
+        assert_eq!(keysym_char("Udiaeresis"), Some('Ü'));
+    }
+
+    #[test]
+    fn layouts_follow_includes_and_overrides() {
+        let de = layout("de").unwrap();
+        // z and y swap places, and @ moves from shift+2 to AltGr+q
+        assert_eq!(de.lookup('z'), key(21, 0));
+        assert_eq!(de.lookup('Y'), key(44, 1));
+        assert_eq!(de.lookup('@'), key(16, 2));
+        assert_eq!(de.lookup('"'), key(3, 1));
+        assert_eq!(de.lookup('ß'), key(12, 0));
+        assert_eq!(de.lookup('\\'), key(12, 2));
+        assert_eq!(de.lookup('ẞ'), None);
+        assert_eq!(de.lookup('€'), key(18, 2));
+        assert_eq!(de.lookup('#'), key(43, 0));
+        assert_eq!(de.lookup('|'), key(86, 2));
+        assert_eq!(de.lookup(' '), key(57, 0));
+        // NoSymbol leaves latin's « in place, but a dead key replaces a
+        assert_eq!(de.lookup('«'), key(44, 2));
+        assert_eq!(de.lookup('a'), None);
+        assert_eq!(de.lookup('æ'), key(30, 2));
+        assert_eq!(de.lookup('@').unwrap().modifiers(), vec![Modifier::AltGr]);
+    }
+
+    #[test]
+    fn variants_are_sections() {
+        let de = layout("de(nodeadkeys)").unwrap();
+        assert_eq!(de.lookup('a'), key(30, 0));
+        assert_eq!(de.lookup('z'), key(21, 0));
+        assert_eq!(layout("latin").unwrap().lookup('z'), key(44, 0));
+        assert!(layout("de(dvorak)").is_err());
+        assert!(layout("fr").is_err());
+        assert!(layout("../de").is_err());
+    }
+}
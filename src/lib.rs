@@ -0,0 +1,92 @@
+//! Automated keyboard and mouse input for Linux.
+//!
+//! Input events are described by [`EventSpec`]s, queued on an
+//! [`InputEventQueue`], and injected by an [`InputBackend`]. The `uinput`
+//! and `x11` backends are available behind the features of the same name,
+//! [`DryRunBackend`] records input without injecting it, and any other type
+//! implementing [`InputBackend`] can be driven by the same scheduler.
+//!
+//! ```
+//! use clickrs::{EventSpec, InputBackend, InputEvent, InputEventQueue, ScrollDirection};
+//!
+//! #[derive(Default)]
+//! struct Recorder(Vec<String>);
+//!
+//! impl InputBackend for Recorder {
+//!     fn key_down(&mut self, key: &str) -> anyhow::Result<()> {
+//!         self.0.push(format!("{} down", key));
+//!         Ok(())
+//!     }
+//!
+//!     fn key_up(&mut self, key: &str) -> anyhow::Result<()> {
+//!         self.0.push(format!("{} up", key));
+//!         Ok(())
+//!     }
+//!
+//!     fn button_down(&mut self, button: u8) -> anyhow::Result<()> {
+//!         self.0.push(format!("button {} down", button));
+//!         Ok(())
+//!     }
+//!
+//!     fn button_up(&mut self, button: u8) -> anyhow::Result<()> {
+//!         self.0.push(format!("button {} up", button));
+//!         Ok(())
+//!     }
+//!
+//!     fn move_by(&mut self, dx: i32, dy: i32) -> anyhow::Result<()> {
+//!         self.0.push(format!("move by {},{}", dx, dy));
+//!         Ok(())
+//!     }
+//!
+//!     fn move_to(&mut self, x: i32, y: i32) -> anyhow::Result<()> {
+//!         self.0.push(format!("move to {},{}", x, y));
+//!         Ok(())
+//!     }
+//!
+//!     fn scroll(&mut self, direction: ScrollDirection, amount: u32) -> anyhow::Result<()> {
+//!         self.0.push(format!("scroll {} {}", direction, amount));
+//!         Ok(())
+//!     }
+//!
+//!     fn paused(&self) -> bool {
+//!         false
+//!     }
+//! }
+//!
+//! let mut queue = InputEventQueue::new(Recorder::default());
+//! queue.add_event(InputEvent::from(EventSpec::parse_key("a:1ms")?));
+//! queue.run_next()?;
+//! assert_eq!(queue.backend().0, vec!["a down", "a up"]);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod backend;
+pub mod buttons;
+pub mod chord;
+pub mod clock;
+pub mod config;
+pub mod dryrun;
+pub mod errors;
+pub mod eventspec;
+pub mod keys;
+pub mod layout;
+pub mod scheduler;
+pub mod script;
+#[cfg(feature = "uinput")]
+pub mod uinput;
+#[cfg(feature = "x11")]
+pub mod x11;
+
+pub use crate::backend::{BackendKind, InputBackend, ScreenSize, UnicodeInput};
+pub use crate::chord::{Chord, Modifier};
+pub use crate::clock::{Clock, RealClock, VirtualClock};
+pub use crate::dryrun::DryRunBackend;
+pub use crate::errors::Error;
+pub use crate::eventspec::{
+    Click, Drag, EventSpec, Jitter, Keystroke, Motion, Move, Schedule, Scroll, ScrollDirection,
+    Sequence, Step, Text, WHEEL_DETENT,
+};
+pub use crate::layout::Layout;
+pub use crate::scheduler::{
+    process_events, CatchUp, InputEvent, InputEventQueue, InputType, PauseSource, RunOptions,
+};
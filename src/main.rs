@@ -1,24 +1,195 @@
+use std::path::PathBuf;
+
 use anyhow::{Context, Result};
-use clap::{crate_authors, crate_description, crate_name, crate_version, value_parser, ArgAction};
+use clap::parser::ValueSource;
+use clap::{
+    crate_authors, crate_description, crate_name, crate_version, value_parser, ArgAction,
+    ArgMatches,
+};
 use flexi_logger::Logger;
 use log::{debug, info, warn};
 
-mod errors;
-mod eventspec;
+use clickrs::config::{Config, DurationSetting, Profile};
+use clickrs::eventspec::parse_duration;
+use clickrs::script;
 #[cfg(feature = "uinput")]
-mod uinput;
+use clickrs::uinput;
 #[cfg(feature = "x11")]
-mod x11;
-
-use crate::eventspec::EventSpec;
+use clickrs::x11;
+use clickrs::{
+    process_events, BackendKind, CatchUp, DryRunBackend, Error, EventSpec, PauseSource, RunOptions,
+    UnicodeInput,
+};
+#[cfg(feature = "uinput")]
+use clickrs::{Layout, ScreenSize};
 
 // Start logging this crate at "warn" verbosity
 const BASE_VERBOSITY: u8 = 2;
 
+/// The value of the option `id`: as given on the command line, or else the
+/// profile's `setting`, or else the option's default.
+fn option<T: Clone + Send + Sync + 'static>(
+    matches: &ArgMatches,
+    id: &str,
+    setting: Option<T>,
+) -> Option<T> {
+    if matches.value_source(id) == Some(ValueSource::CommandLine) {
+        return matches.get_one::<T>(id).cloned();
+    }
+    setting.or_else(|| matches.get_one::<T>(id).cloned())
+}
+
+/// The value of the option `id`, taken as [`option`] and parsed. A profile's
+/// value that doesn't parse is reported as a bad setting of `profile_name`.
+fn parsed_option<T: std::str::FromStr<Err = Error>>(
+    matches: &ArgMatches,
+    id: &str,
+    setting: Option<String>,
+    profile_name: Option<&String>,
+) -> Result<Option<T>> {
+    match setting {
+        Some(setting) if matches.value_source(id) != Some(ValueSource::CommandLine) => setting
+            .parse()
+            .map(Some)
+            .map_err(|e| profile_error(profile_name, id, e)),
+        _ => Ok(matches
+            .get_one::<String>(id)
+            .map(|v| v.parse())
+            .transpose()?),
+    }
+}
+
+/// The value of the duration option `id`, taken as [`parsed_option`] does.
+fn duration_option(
+    matches: &ArgMatches,
+    id: &str,
+    setting: &Option<DurationSetting>,
+    profile_name: Option<&String>,
+) -> Result<Option<std::time::Duration>> {
+    match setting {
+        Some(setting) if matches.value_source(id) != Some(ValueSource::CommandLine) => setting
+            .duration()
+            .map(Some)
+            .map_err(|e| profile_error(profile_name, id, e)),
+        _ => matches
+            .get_one::<String>(id)
+            .map(|v| parse_duration(v))
+            .transpose(),
+    }
+}
+
+/// `e`, reported against the setting for the option `id` in the profile
+/// `profile_name`.
+fn profile_error(
+    profile_name: Option<&String>,
+    id: &str,
+    e: impl std::fmt::Display,
+) -> anyhow::Error {
+    Error::ProfileSetting(
+        profile_name.cloned().unwrap_or_default(),
+        id.replace('_', "-"),
+        e.to_string(),
+    )
+    .into()
+}
+
+/// Print the profiles in `config`, or say there aren't any.
+fn list_profiles(config: Option<&Config>, path: Option<&PathBuf>) {
+    let config = match config {
+        Some(config) if !config.profiles.is_empty() => config,
+        Some(config) => {
+            println!("No profiles in {}", config.path.display());
+            return;
+        }
+        None => {
+            match path {
+                Some(path) => println!("No configuration file at {}", path.display()),
+                None => println!("No configuration file"),
+            }
+            return;
+        }
+    };
+    println!("Profiles in {}:", config.path.display());
+    let width = config.profiles.keys().map(String::len).max().unwrap_or(0);
+    for (name, profile) in &config.profiles {
+        let events = match profile.event_count() {
+            1 => "1 event".to_owned(),
+            n => format!("{} events", n),
+        };
+        let summary = match (&profile.script, profile.event_count()) {
+            (Some(script), 0) => format!("script {}", script.display()),
+            (Some(script), _) => format!("{} and script {}", events, script.display()),
+            (None, _) => events,
+        };
+        match &profile.description {
+            Some(description) => {
+                println!(
+                    "  {:width$}  {} ({})",
+                    name,
+                    description,
+                    summary,
+                    width = width
+                )
+            }
+            None => println!("  {:width$}  ({})", name, summary, width = width),
+        }
+    }
+}
+
 fn main() -> Result<()> {
+    let backend_names: Vec<&'static str> = BackendKind::ALL
+        .iter()
+        .map(BackendKind::name)
+        .chain(std::iter::once(BackendKind::AUTO))
+        .collect();
+    let catch_up_names: Vec<&'static str> = CatchUp::ALL.iter().map(CatchUp::name).collect();
+    let unicode_input_names: Vec<&'static str> =
+        UnicodeInput::ALL.iter().map(UnicodeInput::name).collect();
+    let pause_source_names: Vec<&'static str> =
+        PauseSource::ALL.iter().map(PauseSource::name).collect();
     let mut app = clap::command!("")
+        .arg(
+            clap::Arg::new("config")
+                .global(true)
+                .long("config")
+                .help("The configuration file to read profiles from. Default: ~/.config/clickrs/config.toml.")
+                .value_name("FILE")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            clap::Arg::new("profile")
+                .global(true)
+                .short('p')
+                .long("profile")
+                .help("Run the events and settings of a profile in the configuration file. Options given on the command line override its settings, and events replace its events.")
+                .value_name("NAME")
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("backend")
+                .global(true)
+                .short('b')
+                .long("backend")
+                .help("The input backend to use. 'auto' picks one based on the session.")
+                .value_name("NAME")
+                .required(false)
+                .value_parser(backend_names.clone())
+                .default_value(BackendKind::AUTO),
+        )
+        .arg(
+            clap::Arg::new("dry_run_output")
+                .global(true)
+                .short('o')
+                .long("dry-run-output")
+                .help("File to record dry-run backend input to. Default: stdout.")
+                .value_name("FILE")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
         .arg(
             clap::Arg::new("displayname")
+                .global(true)
                 .short('x')
                 .long("x11-display")
                 .help("The X11 display to send the input to. Default: DISPLAY env var.")
@@ -26,21 +197,105 @@ fn main() -> Result<()> {
                 .required(false),
         )
         .arg(
-            clap::Arg::new("initial_delay_ms")
+            clap::Arg::new("unicode_input")
+                .global(true)
+                .long("unicode-input")
+                .help("How the uinput backend types characters that aren't on a US keyboard: not at all (none), or with the ctrl+shift+u input method of IBus and GTK (ctrl-shift-u).")
+                .value_name("METHOD")
+                .required(false)
+                .value_parser(unicode_input_names)
+                .default_value(UnicodeInput::None.name()),
+        )
+        .arg(
+            clap::Arg::new("layout")
+                .global(true)
+                .long("layout")
+                .help("The XKB keyboard layout the uinput backend types characters with, e.g. de or fr(bepo). Default: the system's layout.")
+                .value_name("LAYOUT")
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("screen_size")
+                .global(true)
+                .long("screen-size")
+                .help("The size of the screen, e.g. 1920x1080, which the uinput backend needs to move the pointer to a screen position. Default: the size of the only connected display.")
+                .value_name("WIDTHxHEIGHT")
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("delay")
+                .global(true)
                 .short('d')
                 .long("delay")
-                .help("Delay in msecs before sending any input events.")
-                .value_name("N")
+                .help("Delay before sending any input events, e.g. 250ms or 2s. A bare number is in msecs.")
+                .value_name("DURATION")
                 .required(false)
-                .value_parser(value_parser!(u64))
                 .default_value("250"),
         )
+        .arg(
+            clap::Arg::new("catch_up")
+                .global(true)
+                .short('c')
+                .long("catch-up")
+                .help("What to do with events missed while falling behind: fire them all (burst), drop them (skip), or fire once (coalesce). Events can set their own with catch-up=POLICY.")
+                .value_name("POLICY")
+                .required(false)
+                .value_parser(catch_up_names)
+                .default_value(CatchUp::Burst.name()),
+        )
+        .arg(
+            clap::Arg::new("pause_source")
+                .global(true)
+                .long("pause-source")
+                .help("What pauses input: numlock being off (numlock), or nothing (none).")
+                .value_name("SOURCE")
+                .required(false)
+                .value_parser(pause_source_names)
+                .default_value(PauseSource::NumLock.name()),
+        )
+        .arg(
+            clap::Arg::new("seed")
+                .global(true)
+                .short('s')
+                .long("seed")
+                .help("Seed for the random interval jitter, for reproducible runs.")
+                .value_name("N")
+                .required(false)
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            clap::Arg::new("hold")
+                .global(true)
+                .long("hold")
+                .help("How long to hold keys and buttons down, unless set per event. Default: 0.")
+                .value_name("DURATION")
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("max_events")
+                .global(true)
+                .short('n')
+                .long("max-events")
+                .help("Exit after sending N events in total.")
+                .value_name("N")
+                .required(false)
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            clap::Arg::new("max_runtime")
+                .global(true)
+                .short('t')
+                .long("max-runtime")
+                .help("Exit after running for this long (e.g. 90s, 1h30m).")
+                .value_name("DURATION")
+                .required(false),
+        )
         .arg(
             clap::Arg::new("mousebutton_and_interval")
                 .short('m')
                 .long("mousebutton-and-interval")
-                .help("Click mouse button X at regular intervals, with Y msecs between.")
-                .value_name("X:Y")
+                .help("Click mouse button X (1-based number, or left, middle, right, side, extra, forward, back, task) at regular intervals of Y (e.g. 500ms, 1.5s, 10cps), +/- up to Z, moving the pointer first if @POS is given. Options: count=N, hold=DURATION, clicks=N, gap=DURATION, catch-up=POLICY.")
+                .value_name("X[@POS]:Y[~Z][,OPTION=V]")
                 .action(ArgAction::Append)
                 .required(false),
         )
@@ -48,17 +303,62 @@ fn main() -> Result<()> {
             clap::Arg::new("keypress_and_interval")
                 .short('k')
                 .long("keypress-and-interval")
-                .help("Press keyboard key X at regular intervals, with Y msecs between.")
-                .value_name("X:Y")
+                .help("Press keyboard key X at regular intervals of Y (e.g. 500ms, 1.5s, 10cps), +/- up to Z. Options: count=N, hold=DURATION, repeat=N, gap=DURATION, catch-up=POLICY.")
+                .value_name("X:Y[~Z][,OPTION=V]")
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("type")
+                .long("type")
+                .help("Type TEXT a key at a time at regular intervals of Y, +/- up to Z. TEXT runs up to the last colon, and \\n and \\t type Return and Tab. Options: count=N, hold=DURATION, delay=DURATION (between keys, default 30ms), catch-up=POLICY.")
+                .value_name("TEXT:Y[~Z][,OPTION=V]")
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("sequence")
+                .long("sequence")
+                .help("Run STEPS one after another at regular intervals of Y, +/- up to Z, with nothing else sent in between. Steps are separated by commas: a key or chord, wait DURATION, click B [at X,Y], move X,Y [over DURATION], scroll DIRECTION N [over DURATION], drag B [from X,Y] to X,Y [over DURATION], or type \"TEXT\". Options: count=N, hold=DURATION, repeat=N, gap=DURATION, catch-up=POLICY.")
+                .value_name("STEPS:Y[~Z][,OPTION=V]")
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("event")
+                .short('e')
+                .long("event")
+                .help("Send EVENT at regular intervals of Y, +/- up to Z. Events: move:X,Y to move the pointer to a screen position, move:+X,-Y to move it relative to where it is, scroll:DIRECTION:N to scroll up, down, left or right by N (possibly fractional) wheel detents, drag:B:[X,Y:]X,Y to drag with mouse button B, optionally from a starting point. Options: count=N, hold=DURATION (accepted as for other events, but a drag holds its button until it ends), repeat=N, gap=DURATION, over=DURATION (for moves, scrolls and drags), catch-up=POLICY.")
+                .value_name("EVENT:Y[~Z][,OPTION=V]")
                 .action(ArgAction::Append)
                 .required(false),
         )
         .arg(
             clap::Arg::new("verbose")
+                .global(true)
                 .short('v')
                 .long("verbose")
                 .action(clap::ArgAction::Count)
                 .help("show informational output, repeat for increasing verbosity"),
+        )
+        .subcommand(
+            clap::Command::new("run")
+                .about("Run the events described in a script file, along with any given on the command line.")
+                .arg(
+                    clap::Arg::new("script")
+                        .help("The script to run.")
+                        .value_name("FILE")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("profiles")
+                .about("Work with the profiles in the configuration file.")
+                .subcommand_required(true)
+                .subcommand(
+                    clap::Command::new("list").about("List the profiles in the configuration file."),
+                ),
         );
 
     let matches = app.get_matches_mut();
@@ -104,52 +404,197 @@ fn main() -> Result<()> {
     info!("{}", crate_description!());
     info!("Created by {}", crate_authors!());
 
-    if !matches.contains_id("mousebutton_and_interval")
-        && !matches.contains_id("keypress_and_interval")
-    {
+    // A configuration file given with --config has to exist, and so does the
+    // default one if a profile is asked for
+    let config_path = matches
+        .get_one::<PathBuf>("config")
+        .cloned()
+        .or_else(Config::default_path);
+    let config = match config_path {
+        Some(ref path)
+            if matches.contains_id("config") || matches.contains_id("profile") || path.exists() =>
+        {
+            Some(Config::load(path)?)
+        }
+        _ => None,
+    };
+
+    if matches.subcommand_matches("profiles").is_some() {
+        list_profiles(config.as_ref(), config_path.as_ref());
+        return Ok(());
+    }
+
+    let profile_name = matches.get_one::<String>("profile");
+    let profile = match (profile_name, &config) {
+        (Some(name), Some(config)) => {
+            info!("Using profile {} from {}", name, config.path.display());
+            config.profile(name)?.clone()
+        }
+        (Some(_), None) => return Err(Error::NoConfigFile.into()),
+        (None, _) => Profile::default(),
+    };
+
+    let cli_events = matches.contains_id("mousebutton_and_interval")
+        || matches.contains_id("keypress_and_interval")
+        || matches.contains_id("event")
+        || matches.contains_id("type")
+        || matches.contains_id("sequence")
+        || matches.subcommand_matches("run").is_some();
+    if !cli_events && profile.event_count() == 0 && profile.script.is_none() {
         warn!("No events specified.  Nothing to do...");
         println!("{}", app.render_usage());
         return Ok(());
     }
 
     let mut eventspecs: Vec<EventSpec> = Vec::with_capacity(2);
-    let mouse_events = matches
-        .get_many::<String>("mousebutton_and_interval")
-        .unwrap_or_default()
-        .map(|v| v.as_str())
-        .map(EventSpec::parse_mouse)
-        .collect::<Result<Vec<EventSpec>>>()?;
-    if mouse_events.is_empty() {
-        warn!("No mousebutton events specified.");
-    } else {
-        eventspecs.extend(mouse_events);
-    }
+    // Events on the command line replace the profile's
+    if cli_events {
+        let mouse_events = matches
+            .get_many::<String>("mousebutton_and_interval")
+            .unwrap_or_default()
+            .map(|v| v.as_str())
+            .map(EventSpec::parse_mouse)
+            .collect::<Result<Vec<EventSpec>>>()?;
+        if mouse_events.is_empty() {
+            warn!("No mousebutton events specified.");
+        } else {
+            eventspecs.extend(mouse_events);
+        }
+
+        let keyboard_events = matches
+            .get_many::<String>("keypress_and_interval")
+            .unwrap_or_default()
+            .map(|v| v.as_str())
+            .map(EventSpec::parse_key)
+            .collect::<Result<Vec<EventSpec>>>()?;
+        if keyboard_events.is_empty() {
+            warn!("No key events specified.");
+        } else {
+            eventspecs.extend(keyboard_events);
+        }
+
+        let other_events = matches
+            .get_many::<String>("event")
+            .unwrap_or_default()
+            .map(|v| v.as_str())
+            .map(EventSpec::parse_event)
+            .collect::<Result<Vec<EventSpec>>>()?;
+        eventspecs.extend(other_events);
+
+        let text_events = matches
+            .get_many::<String>("type")
+            .unwrap_or_default()
+            .map(|v| v.as_str())
+            .map(EventSpec::parse_text)
+            .collect::<Result<Vec<EventSpec>>>()?;
+        eventspecs.extend(text_events);
 
-    let keyboard_events = matches
-        .get_many::<String>("keypress_and_interval")
-        .unwrap_or_default()
-        .map(|v| v.as_str())
-        .map(EventSpec::parse_key)
-        .collect::<Result<Vec<EventSpec>>>()?;
-    if keyboard_events.is_empty() {
-        warn!("No key events specified.");
+        let sequence_events = matches
+            .get_many::<String>("sequence")
+            .unwrap_or_default()
+            .map(|v| v.as_str())
+            .map(EventSpec::parse_sequence)
+            .collect::<Result<Vec<EventSpec>>>()?;
+        eventspecs.extend(sequence_events);
+
+        if let Some(run) = matches.subcommand_matches("run") {
+            let path = run
+                .get_one::<PathBuf>("script")
+                .expect("Programming Error: This argument is required, so there should always be a value present");
+            eventspecs.extend(script::load(path)?);
+        }
     } else {
-        eventspecs.extend(keyboard_events);
+        let base = config
+            .as_ref()
+            .map_or(std::path::Path::new("."), Config::base);
+        eventspecs.extend(profile.eventspecs(base)?);
     }
 
-    let start_delay_ms: u64 = *matches
-        .get_one::<u64>("initial_delay_ms")
+    let start_delay = duration_option(&matches, "delay", &profile.delay, profile_name)?
+        .expect("Programming Error: Default was specified for this flag, so there should always be a value present");
+
+    let catch_up: CatchUp = parsed_option(&matches, "catch_up", profile.catch_up.clone(), profile_name)?
         .expect("Programming Error: Default was specified for this flag, so there should always be a value present");
 
-    #[cfg(feature = "x11")]
-    x11::process_events(
-        matches.value_of("displayname").map(|str| str.to_owned()),
-        eventspecs,
-        std::time::Duration::from_millis(start_delay_ms),
-    )?;
+    let pause_source: PauseSource = parsed_option(&matches, "pause_source", profile.pause_source.clone(), profile_name)?
+        .expect("Programming Error: Default was specified for this flag, so there should always be a value present");
+
+    let max_runtime = duration_option(&matches, "max_runtime", &profile.max_runtime, profile_name)?;
+
+    let hold = duration_option(&matches, "hold", &profile.hold, profile_name)?.unwrap_or_default();
 
-    #[cfg(feature = "uinput")]
-    uinput::process_events(eventspecs, std::time::Duration::from_millis(start_delay_ms))?;
+    let options = RunOptions {
+        start_delay,
+        catch_up,
+        pause_source,
+        seed: option(&matches, "seed", profile.seed),
+        max_events: option(&matches, "max_events", profile.max_events),
+        max_runtime,
+        hold,
+    };
+
+    let displayname = option(&matches, "displayname", profile.x11_display.clone());
+    let backend_name = option(&matches, "backend", profile.backend.clone())
+        .expect("Programming Error: Default was specified for this flag, so there should always be a value present");
+    // Unlike the command line, nothing has checked a profile's backend yet
+    if !backend_names.contains(&backend_name.as_str()) {
+        return Err(profile_error(
+            profile_name,
+            "backend",
+            format!("{} (expected {})", backend_name, backend_names.join(", ")),
+        ));
+    }
+    let backend = BackendKind::select(&backend_name, displayname.as_deref())?;
+    info!("Using {} backend", backend);
+
+    match backend {
+        BackendKind::DryRun => {
+            let dry_run = match option(&matches, "dry_run_output", profile.dry_run_output.clone()) {
+                Some(path) => DryRunBackend::to_file(path)?,
+                None => DryRunBackend::to_stdout(),
+            };
+            process_events(dry_run, eventspecs, &options)?
+        }
+        #[cfg(feature = "uinput")]
+        BackendKind::UInput => {
+            let mut uinput = uinput::UInputBackend::new()?;
+            uinput.set_unicode_input(
+                parsed_option(&matches, "unicode_input", profile.unicode_input.clone(), profile_name)?
+                    .expect("Programming Error: Default was specified for this flag, so there should always be a value present"),
+            );
+            // A layout that was asked for has to load, but the system's
+            // only needs to if it can
+            let layout = match option(&matches, "layout", profile.layout.clone()) {
+                Some(name) => Some(Layout::load(&name)?),
+                None => Layout::load(&Layout::system_default())
+                    .map_err(|e| warn!("{} Typing as on a US layout.", e))
+                    .ok(),
+            };
+            if let Some(layout) = &layout {
+                info!("Typing with the {} keyboard layout", layout);
+            }
+            uinput.set_layout(layout);
+            let screen_size = parsed_option(
+                &matches,
+                "screen_size",
+                profile.screen_size.clone(),
+                profile_name,
+            )?
+            .or_else(ScreenSize::detect);
+            match screen_size {
+                Some(size) => {
+                    info!("Moving the pointer on a {} screen", size);
+                    uinput.set_screen_size(size)?;
+                }
+                None => debug!("Screen size is unknown, so the pointer can only move relatively"),
+            }
+            process_events(uinput, eventspecs, &options)?
+        }
+        #[cfg(feature = "x11")]
+        BackendKind::X11 => {
+            process_events(x11::X11Backend::new(displayname)?, eventspecs, &options)?
+        }
+    }
 
     Ok(())
 }
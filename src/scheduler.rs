@@ -0,0 +1,1848 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::backend::InputBackend;
+use crate::clock::{Clock, RealClock};
+use crate::errors::Error;
+use crate::eventspec::{
+    Click, Drag, EventSpec, Jitter, Keystroke, Motion, Move, Schedule, Scroll, ScrollDirection,
+    Sequence, Step, Text,
+};
+
+/// Time between the steps of a pointer path or a gradual scroll.
+const PATH_STEP: Duration = Duration::from_millis(10);
+
+/// The input emitted each time an event fires.
+#[derive(Debug, Clone)]
+pub enum InputType {
+    /// A key, named as it was given on the command line.
+    Keyboard(String),
+    /// A mouse button click.
+    Mouse(Click),
+    /// A pointer movement.
+    Move(Move),
+    /// A turn of the scroll wheel.
+    Scroll(Scroll),
+    /// A mouse button held down while the pointer moves.
+    Drag(Drag),
+    /// Text typed a key at a time.
+    Type(Text),
+    /// Steps run one after another.
+    Sequence(Sequence),
+}
+
+impl InputType {
+    /// The input for a step of a sequence, or `None` for a wait.
+    fn from_step(step: &Step) -> Option<Self> {
+        match *step {
+            Step::Key(ref key) => Some(InputType::Keyboard(key.clone())),
+            Step::Click(click) => Some(InputType::Mouse(click)),
+            Step::Move(movement) => Some(InputType::Move(movement)),
+            Step::Scroll(scroll) => Some(InputType::Scroll(scroll)),
+            Step::Drag(drag) => Some(InputType::Drag(drag)),
+            Step::Type(ref text) => Some(InputType::Type(text.clone())),
+            Step::Wait(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for InputType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            InputType::Keyboard(ref key) => write!(f, "key {:>8}", key),
+            InputType::Mouse(ref click) => write!(f, "{}", click),
+            InputType::Move(ref movement) => write!(f, "{}", movement),
+            InputType::Scroll(ref scroll) => write!(f, "{}", scroll),
+            InputType::Drag(ref drag) => write!(f, "{}", drag),
+            InputType::Type(ref text) => write!(f, "{}", text),
+            InputType::Sequence(ref sequence) => write!(f, "{}", sequence),
+        }
+    }
+}
+
+/// A key or button that can be held down.
+#[derive(Debug, Clone)]
+enum Held {
+    Key(String),
+    Button(u8),
+}
+
+impl std::fmt::Display for Held {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Held::Key(ref key) => write!(f, "key {}", key),
+            Held::Button(button) => write!(f, "button {}", button),
+        }
+    }
+}
+
+fn duration_as_f32(duration: Duration) -> f32 {
+    (duration.as_secs() as f32) + ((duration.subsec_nanos() as f32) / 1000000000.0)
+}
+
+/// A periodic input event, as stored in an [`InputEventQueue`].
+#[derive(Debug, Clone)]
+pub struct InputEvent {
+    /// The input to emit.
+    pub event: InputType,
+    /// Time between firings.
+    pub interval: Duration,
+    /// Random variation applied to each interval.
+    pub jitter: Option<Jitter>,
+    /// Firings left before the event is retired, or `None` to repeat forever.
+    pub count: Option<u64>,
+    /// How long to hold the key or button down, or `None` for the queue's
+    /// default.
+    pub hold: Option<Duration>,
+    /// Number of times to repeat the input each firing.
+    pub repeat: u32,
+    /// Time between repetitions.
+    pub gap: Duration,
+    /// What to do with missed firings, or `None` for the queue's default.
+    pub catch_up: Option<CatchUp>,
+}
+
+impl InputEvent {
+    /// Fire `event` on `schedule`.
+    pub fn new(event: InputType, schedule: Schedule) -> Self {
+        InputEvent {
+            event,
+            interval: schedule.interval,
+            jitter: schedule.jitter,
+            count: schedule.count,
+            hold: schedule.hold,
+            repeat: schedule.repeat,
+            gap: schedule.gap,
+            catch_up: schedule.catch_up,
+        }
+    }
+
+    /// The time until the next firing, with jitter drawn from `rng`.
+    fn next_interval<R: Rng>(&self, rng: &mut R) -> Duration {
+        let offset = match self.jitter {
+            None => return self.interval,
+            Some(Jitter::Uniform(range)) => {
+                let range = range.as_secs_f64();
+                rng.gen_range(-range..=range)
+            }
+            Some(Jitter::Gaussian(sigma)) => {
+                // Box-Muller transform; 1 - u keeps ln() away from zero
+                let u1: f64 = 1.0 - rng.gen::<f64>();
+                let u2: f64 = rng.gen();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                z * sigma.as_secs_f64()
+            }
+        };
+        Duration::from_secs_f64((self.interval.as_secs_f64() + offset).max(0.0))
+    }
+}
+
+impl std::fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} every {:?}", self.event, self.interval)?;
+        if let Some(ref jitter) = self.jitter {
+            write!(f, " ({})", jitter)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ", {} more times", count)?;
+        }
+        if let Some(hold) = self.hold {
+            write!(f, ", held {:?}", hold)?;
+        }
+        if self.repeat > 1 {
+            write!(f, ", {} times {:?} apart", self.repeat, self.gap)?;
+        }
+        if let Some(catch_up) = self.catch_up {
+            write!(f, ", catch-up {}", catch_up)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<EventSpec> for InputEvent {
+    fn from(eventspec: EventSpec) -> Self {
+        match eventspec {
+            EventSpec::MouseEvent(click, schedule) => {
+                InputEvent::new(InputType::Mouse(click), schedule)
+            }
+            EventSpec::MoveEvent(movement, schedule) => {
+                InputEvent::new(InputType::Move(movement), schedule)
+            }
+            EventSpec::TypeEvent(text, schedule) => {
+                InputEvent::new(InputType::Type(text), schedule)
+            }
+            EventSpec::DragEvent(drag, schedule) => {
+                InputEvent::new(InputType::Drag(drag), schedule)
+            }
+            EventSpec::ScrollEvent(scroll, schedule) => {
+                InputEvent::new(InputType::Scroll(scroll), schedule)
+            }
+            EventSpec::KeyboardEvent(key, schedule) => {
+                InputEvent::new(InputType::Keyboard(key), schedule)
+            }
+            EventSpec::SequenceEvent(sequence, schedule) => {
+                InputEvent::new(InputType::Sequence(sequence), schedule)
+            }
+        }
+    }
+}
+
+/// What to do with firings that were missed because the scheduler fell behind,
+/// e.g. while the machine was suspended, which [`RealClock`] counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CatchUp {
+    /// Fire every missed slot, back to back.
+    #[default]
+    Burst,
+    /// Drop the missed slots and wait for the next one.
+    Skip,
+    /// Fire once for all the missed slots, then wait for the next one.
+    Coalesce,
+}
+
+impl CatchUp {
+    pub const ALL: &'static [CatchUp] = &[CatchUp::Burst, CatchUp::Skip, CatchUp::Coalesce];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            CatchUp::Burst => "burst",
+            CatchUp::Skip => "skip",
+            CatchUp::Coalesce => "coalesce",
+        }
+    }
+}
+
+impl std::fmt::Display for CatchUp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for CatchUp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|policy| policy.name() == s)
+            .ok_or_else(|| Error::CatchUpPolicy(s.to_owned()))
+    }
+}
+
+/// What pauses input while a run is going.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PauseSource {
+    /// Pause while the backend reports numlock off.
+    #[default]
+    NumLock,
+    /// Never pause.
+    None,
+}
+
+impl PauseSource {
+    pub const ALL: &'static [PauseSource] = &[PauseSource::NumLock, PauseSource::None];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            PauseSource::NumLock => "numlock",
+            PauseSource::None => "none",
+        }
+    }
+}
+
+impl std::fmt::Display for PauseSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for PauseSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|source| source.name() == s)
+            .ok_or_else(|| Error::PauseSource(s.to_owned()))
+    }
+}
+
+/// Something the queue has to do when a deadline comes up.
+#[derive(Debug)]
+enum Pending {
+    /// Fire a periodic event.
+    Fire(InputEvent),
+    /// Release a key or button that's being held down.
+    Release(Held),
+    /// Take one step along a pointer path.
+    Step(Motion),
+    /// Take one step of a gradual scroll.
+    ScrollStep(ScrollDirection, u32),
+}
+
+/// An action waiting in the queue for its deadline.
+///
+/// Actions due at the same instant are ordered by `seq`, so they run in the
+/// order they were scheduled.
+#[derive(Debug)]
+struct ScheduledEvent {
+    deadline: Instant,
+    seq: u64,
+    action: Pending,
+}
+
+impl PartialEq for ScheduledEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScheduledEvent {}
+
+impl PartialOrd for ScheduledEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.deadline, self.seq).cmp(&(other.deadline, other.seq))
+    }
+}
+
+/// Schedules periodic [`InputEvent`]s and emits them through an [`InputBackend`].
+///
+/// Each event has an absolute deadline, and is rescheduled one interval after
+/// its previous deadline rather than after the time it actually fired, so
+/// schedules don't drift even when the backend is slow. What happens to slots
+/// missed while falling further behind than that is set by [`CatchUp`]. Time
+/// is kept by a [`Clock`], which is the system clock unless another is given
+/// to [`InputEventQueue::with_clock`].
+///
+/// Keys and buttons that are held down are released by their own deadline in
+/// the queue, so a long hold doesn't delay any other event. The repetitions
+/// of a burst, like a double click, are the exception: they make up a single
+/// firing, and nothing else is sent until the last one has been pressed.
+pub struct InputEventQueue<B: InputBackend, C: Clock = RealClock> {
+    backend: B,
+    clock: C,
+    events: BinaryHeap<Reverse<ScheduledEvent>>,
+    next_seq: u64,
+    active: usize,
+    default_hold: Duration,
+    catch_up: CatchUp,
+    pause_source: PauseSource,
+    rng: StdRng,
+    fired: u64,
+    max_events: Option<u64>,
+    max_runtime: Option<Duration>,
+    stop_at: Option<Instant>,
+}
+
+impl<B: InputBackend + std::fmt::Debug, C: Clock> std::fmt::Debug for InputEventQueue<B, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Backend: {:?}, catch-up: {}, events: {:?}",
+            &self.backend, self.catch_up, &self.events
+        )
+    }
+}
+
+impl<B: InputBackend> InputEventQueue<B> {
+    /// Create an empty queue that emits input through `backend`.
+    pub fn new(backend: B) -> Self {
+        Self::with_clock(backend, RealClock)
+    }
+}
+
+impl<B: InputBackend, C: Clock> InputEventQueue<B, C> {
+    /// Create an empty queue that emits input through `backend`, keeping time
+    /// with `clock`.
+    pub fn with_clock(backend: B, clock: C) -> Self {
+        InputEventQueue {
+            backend,
+            clock,
+            events: BinaryHeap::new(),
+            next_seq: 0,
+            active: 0,
+            default_hold: Duration::from_millis(0),
+            catch_up: CatchUp::default(),
+            pause_source: PauseSource::default(),
+            rng: StdRng::from_entropy(),
+            fired: 0,
+            max_events: None,
+            max_runtime: None,
+            stop_at: None,
+        }
+    }
+
+    /// Stop once `max_events` events have fired in total.
+    pub fn set_max_events(&mut self, max_events: Option<u64>) {
+        self.max_events = max_events;
+    }
+
+    /// Stop once `max_runtime` has passed since [`InputEventQueue::start`].
+    pub fn set_max_runtime(&mut self, max_runtime: Option<Duration>) {
+        self.max_runtime = max_runtime;
+    }
+
+    /// Hold keys and buttons down for `hold` when an event doesn't set its
+    /// own hold time.
+    pub fn set_default_hold(&mut self, hold: Duration) {
+        self.default_hold = hold;
+    }
+
+    /// Number of events fired so far.
+    pub fn fired(&self) -> u64 {
+        self.fired
+    }
+
+    /// Whether there's nothing left to run: every event has been retired, or
+    /// a limit on the number of events or the runtime has been reached.
+    pub fn finished(&self) -> bool {
+        self.active == 0 || self.limit_reached()
+    }
+
+    /// Whether the limit on the number of events or the runtime has been
+    /// reached.
+    fn limit_reached(&self) -> bool {
+        self.max_events.is_some_and(|max| self.fired >= max)
+            || self
+                .stop_at
+                .is_some_and(|stop_at| self.clock.now() >= stop_at)
+    }
+
+    /// Seed the random number generator used for jitter, to make runs
+    /// reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Set how missed firings are handled, for events that don't set their
+    /// own policy.
+    pub fn set_catch_up(&mut self, catch_up: CatchUp) {
+        self.catch_up = catch_up;
+    }
+
+    /// Set what pauses input.
+    pub fn set_pause_source(&mut self, pause_source: PauseSource) {
+        self.pause_source = pause_source;
+    }
+
+    /// The backend events are emitted through.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    fn schedule(&mut self, action: Pending, deadline: Instant) {
+        debug!("Scheduling {:?} at {:?}", action, deadline);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.events.push(Reverse(ScheduledEvent {
+            deadline,
+            seq,
+            action,
+        }));
+    }
+
+    /// Schedule `event` to fire one interval from now.
+    pub fn add_event(&mut self, event: InputEvent) {
+        let deadline = self.clock.now() + event.next_interval(&mut self.rng);
+        self.active += 1;
+        self.schedule(Pending::Fire(event), deadline);
+    }
+
+    /// Push every deadline back by `delay`, e.g. to skip time spent paused.
+    pub fn postpone(&mut self, delay: Duration) {
+        // Shifting every deadline by the same amount preserves the heap order
+        self.events = self
+            .events
+            .drain()
+            .map(|Reverse(mut scheduled)| {
+                scheduled.deadline += delay;
+                Reverse(scheduled)
+            })
+            .collect();
+    }
+
+    /// Wait until the next event is due, emit it, and reschedule it.
+    pub fn run_next(&mut self) -> Result<()> {
+        let scheduled = match self.events.pop() {
+            None => {
+                // Sleep here in case run_next is being called in a tight loop
+                // this way we yield time to the OS
+                debug!("Nothing to do...");
+                self.clock.sleep(Duration::from_millis(100));
+                return Ok(());
+            }
+            Some(Reverse(scheduled)) => scheduled,
+        };
+        let now = self.clock.now();
+        if let Some(stop_at) = self.stop_at.filter(|stop_at| scheduled.deadline > *stop_at) {
+            // don't sleep past the end of the run
+            self.clock.sleep(stop_at.saturating_duration_since(now));
+            self.events.push(Reverse(scheduled));
+            return Ok(());
+        }
+        let mut overdue = Duration::from_millis(0);
+        if scheduled.deadline > now {
+            // sleep for however much time is left until the next event is ready
+            self.clock.sleep(scheduled.deadline - now);
+        } else {
+            // we're in catch-up time
+            overdue = now - scheduled.deadline;
+            debug!("event is {:?} overdue", overdue);
+        }
+
+        match scheduled.action {
+            Pending::Release(held) => self.release(&held),
+            Pending::Step(motion) => self.backend.move_pointer(motion),
+            Pending::ScrollStep(direction, amount) => self.backend.scroll(direction, amount),
+            Pending::Fire(event) => self.fire(event, scheduled.deadline, overdue),
+        }
+    }
+
+    fn fire(&mut self, mut event: InputEvent, due: Instant, overdue: Duration) -> Result<()> {
+        // Number of later slots for this event that are also already due
+        let mut missed: u32 = 0;
+        if !event.interval.is_zero() {
+            missed =
+                u32::try_from(overdue.as_nanos() / event.interval.as_nanos()).unwrap_or(u32::MAX);
+        }
+
+        let catch_up = event.catch_up.unwrap_or(self.catch_up);
+        let mut deadline = due + event.next_interval(&mut self.rng);
+        match catch_up {
+            CatchUp::Skip if missed > 0 => {
+                info!("Skipping {} missed firings of {}", missed + 1, event.event);
+            }
+            _ => {
+                self.do_event(&event)?;
+                if let Some(ref mut count) = event.count {
+                    *count -= 1;
+                    if *count == 0 {
+                        info!("Retiring {}", event.event);
+                        self.active -= 1;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        if catch_up != CatchUp::Burst {
+            // realign to the first slot that hasn't come due yet
+            deadline += event.interval.saturating_mul(missed);
+        }
+        self.schedule(Pending::Fire(event), deadline);
+        Ok(())
+    }
+
+    fn release(&mut self, held: &Held) -> Result<()> {
+        debug!("Releasing {}", held);
+        match *held {
+            Held::Button(button) => self.backend.button_up(button),
+            Held::Key(ref key) => self.backend.key_up(key),
+        }
+    }
+
+    /// Release every key and button that's being held down.
+    ///
+    /// If `wait` is set, first wait for each hold to run out, finishing any
+    /// pointer paths and gradual scrolls along the way; otherwise those still
+    /// under way are abandoned.
+    pub fn release_held(&mut self, wait: bool) -> Result<()> {
+        self.run_actions(None, wait)
+    }
+
+    /// Run every release and step due by `until`, or every one if `None`,
+    /// without firing any events. With `wait`, each is run at its deadline;
+    /// otherwise releases are run straight away and steps are dropped.
+    fn run_actions(&mut self, until: Option<Instant>, wait: bool) -> Result<()> {
+        let (mut held, pending): (Vec<_>, Vec<_>) = self
+            .events
+            .drain()
+            .map(|Reverse(scheduled)| scheduled)
+            .partition(|scheduled| {
+                !matches!(scheduled.action, Pending::Fire(_))
+                    && until.is_none_or(|until| scheduled.deadline <= until)
+            });
+        self.events = pending.into_iter().map(Reverse).collect();
+        held.sort();
+        let mut result = Ok(());
+        for scheduled in held {
+            if wait {
+                let now = self.clock.now();
+                self.clock
+                    .sleep(scheduled.deadline.saturating_duration_since(now));
+            }
+            match scheduled.action {
+                // keep going after a failure, so nothing else is left stuck
+                Pending::Release(ref held) => result = result.and(self.release(held)),
+                Pending::Step(motion) if wait => {
+                    result = result.and(self.backend.move_pointer(motion))
+                }
+                Pending::ScrollStep(direction, amount) if wait => {
+                    result = result.and(self.backend.scroll(direction, amount))
+                }
+                _ => {}
+            }
+        }
+        result
+    }
+
+    /// Whether input is paused by the [`PauseSource`].
+    pub fn paused(&self) -> bool {
+        match self.pause_source {
+            PauseSource::NumLock => {
+                debug!("Querying pause state");
+                self.backend.paused()
+            }
+            PauseSource::None => false,
+        }
+    }
+
+    /// Run the queue until it's [finished](InputEventQueue::finished),
+    /// waiting for `start_delay` first and idling while paused.
+    ///
+    /// Anything still held down when the run ends is released, even if it
+    /// ends with an error: at once if a limit ended it, or else when its
+    /// hold runs out.
+    pub fn start(&mut self, start_delay: Duration) -> Result<()> {
+        let result = self.run(start_delay);
+        // Holds run their course when the events have all been retired, but
+        // a limit stops the run then and there
+        let released = self.release_held(result.is_ok() && !self.limit_reached());
+        result.and(released)
+    }
+
+    fn run(&mut self, start_delay: Duration) -> Result<()> {
+        self.stop_at = self
+            .max_runtime
+            .map(|max_runtime| self.clock.now() + max_runtime);
+        self.clock.sleep(start_delay);
+        let pause_poll = Duration::from_millis(500);
+        let mut noise_ctl = std::num::Wrapping(0_u64);
+        loop {
+            while !self.paused() {
+                if self.finished() {
+                    info!("Finished after {} events", self.fired);
+                    return Ok(());
+                }
+                self.run_next()?;
+            }
+            if self.finished() {
+                info!("Finished after {} events", self.fired);
+                return Ok(());
+            }
+            // Don't leave anything held down while paused
+            self.release_held(false)?;
+            let paused_at = self.clock.now();
+            if noise_ctl.0.is_multiple_of(10) {
+                info!("Paused...");
+            }
+            noise_ctl += std::num::Wrapping(1_u64);
+            self.clock.sleep(pause_poll);
+            // Time spent paused doesn't count towards any event's interval
+            self.postpone(self.clock.now().saturating_duration_since(paused_at));
+        }
+    }
+
+    fn do_event(&mut self, event: &InputEvent) -> Result<()> {
+        info!(
+            "{} (next in {:2.3}s)",
+            event.event,
+            duration_as_f32(event.interval)
+        );
+        self.fired += 1;
+        let hold = event.hold.unwrap_or(self.default_hold);
+        for repetition in 1..=event.repeat {
+            if repetition > 1 {
+                self.clock.sleep(event.gap);
+            }
+            let done = self.do_input(&event.event, hold)?;
+            if repetition < event.repeat {
+                // Finish this repetition before starting the next
+                self.finish(done)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run everything queued up to happen by `done`, and wait until then.
+    fn finish(&mut self, done: Instant) -> Result<()> {
+        self.run_actions(Some(done), true)?;
+        let now = self.clock.now();
+        self.clock.sleep(done.saturating_duration_since(now));
+        Ok(())
+    }
+
+    /// Send `input`, holding keys and buttons down for `hold`, queueing up
+    /// whatever has to happen later, and return when it will all be done.
+    fn do_input(&mut self, input: &InputType, hold: Duration) -> Result<Instant> {
+        let now = self.clock.now();
+        let held = match *input {
+            InputType::Move(movement) => {
+                self.start_move(movement)?;
+                return Ok(now + movement.over.unwrap_or_default());
+            }
+            InputType::Scroll(scroll) => {
+                self.start_scroll(scroll)?;
+                return Ok(now + scroll.over.unwrap_or_default());
+            }
+            InputType::Drag(drag) => return self.start_drag(drag),
+            InputType::Type(ref text) => return self.type_text(text, hold),
+            InputType::Sequence(ref sequence) => return self.run_sequence(sequence, hold),
+            InputType::Mouse(click) => {
+                if let Some(at) = click.at {
+                    self.backend.move_pointer(at)?;
+                }
+                Held::Button(click.button)
+            }
+            InputType::Keyboard(ref key) => Held::Key(key.clone()),
+        };
+        if hold.is_zero() {
+            match held {
+                Held::Button(button) => self.backend.emit_button(button)?,
+                Held::Key(ref key) => self.backend.emit_key(key)?,
+            }
+            return Ok(self.clock.now());
+        }
+        match held {
+            Held::Button(button) => self.backend.button_down(button)?,
+            Held::Key(ref key) => self.backend.key_down(key)?,
+        }
+        let release_at = self.clock.now() + hold;
+        self.schedule(Pending::Release(held), release_at);
+        Ok(release_at)
+    }
+
+    /// Type `text` a key at a time, holding each one down for `hold`. The
+    /// whole text is typed before anything else is sent, so other events
+    /// can't land in the middle of it.
+    fn type_text(&mut self, text: &Text, hold: Duration) -> Result<Instant> {
+        for (i, stroke) in text.strokes().enumerate() {
+            if i > 0 {
+                self.clock.sleep(text.delay);
+            }
+            let key = match stroke {
+                Keystroke::Key(key) => key,
+                Keystroke::Unicode(c) => {
+                    self.backend.type_unicode(c)?;
+                    continue;
+                }
+            };
+            if hold.is_zero() {
+                self.backend.emit_key(&key)?;
+            } else {
+                self.backend.key_down(&key)?;
+                self.clock.sleep(hold);
+                self.backend.key_up(&key)?;
+            }
+        }
+        Ok(self.clock.now())
+    }
+
+    /// Run the steps of `sequence` in order, finishing each one, holds and
+    /// paths included, before starting the next, so nothing else can land
+    /// in the middle of it.
+    fn run_sequence(&mut self, sequence: &Sequence, hold: Duration) -> Result<Instant> {
+        for step in &sequence.steps {
+            debug!("Sequence step: {}", step);
+            if let Step::Wait(duration) = *step {
+                self.clock.sleep(duration);
+            } else if let Some(input) = InputType::from_step(step) {
+                let done = self.do_input(&input, hold)?;
+                self.finish(done)?;
+            }
+        }
+        Ok(self.clock.now())
+    }
+
+    /// Press a button, move the pointer along a path to the end of the drag,
+    /// and queue up the button's release after the last step, so it's
+    /// released like any held button if the run is paused or fails first.
+    fn start_drag(&mut self, drag: Drag) -> Result<Instant> {
+        if let Some(from) = drag.from {
+            self.backend.move_pointer(from)?;
+        }
+        self.backend.button_down(drag.button)?;
+        let moved = self.start_move(Move {
+            motion: drag.to,
+            over: drag.over,
+        });
+        if moved.is_err() {
+            // don't leave the button stuck down
+            return moved
+                .and(self.backend.button_up(drag.button))
+                .map(|_| self.clock.now());
+        }
+        let release_at = self.clock.now() + drag.over.unwrap_or_default();
+        self.schedule(Pending::Release(Held::Button(drag.button)), release_at);
+        Ok(release_at)
+    }
+
+    /// Move the pointer, either at once or by queueing up the steps of a
+    /// path that gets there after `movement.over`.
+    fn start_move(&mut self, movement: Move) -> Result<()> {
+        let over = match movement.over.filter(|over| !over.is_zero()) {
+            None => return self.backend.move_pointer(movement.motion),
+            Some(over) => over,
+        };
+        let (from, to) = match movement.motion {
+            Motion::By(dx, dy) => ((0, 0), (dx, dy)),
+            Motion::To(x, y) => match self.backend.pointer_position() {
+                Some(from) => (from, (x, y)),
+                None => {
+                    debug!("Pointer position is unknown, jumping to {},{}", x, y);
+                    return self.backend.move_pointer(movement.motion);
+                }
+            },
+        };
+        let distance = (i64::from(to.0) - i64::from(from.0))
+            .abs()
+            .max((i64::from(to.1) - i64::from(from.1)).abs());
+        let steps = path_steps(over, distance as u128);
+        let point = |step: u32| {
+            let lerp = |a: i32, b: i32| {
+                let offset = (i64::from(b) - i64::from(a)) * i64::from(step) / i64::from(steps);
+                (i64::from(a) + offset) as i32
+            };
+            (lerp(from.0, to.0), lerp(from.1, to.1))
+        };
+
+        let start = self.clock.now();
+        let mut previous = from;
+        for step in 1..=steps {
+            let next = point(step);
+            let motion = match movement.motion {
+                Motion::By(..) => Motion::By(next.0 - previous.0, next.1 - previous.1),
+                Motion::To(..) => Motion::To(next.0, next.1),
+            };
+            previous = next;
+            self.schedule(Pending::Step(motion), start + over * step / steps);
+        }
+        Ok(())
+    }
+
+    /// Scroll, either at once or by queueing up steps that add up to
+    /// `scroll.amount` after `scroll.over`.
+    fn start_scroll(&mut self, scroll: Scroll) -> Result<()> {
+        let over = match scroll.over.filter(|over| !over.is_zero()) {
+            None => return self.backend.scroll(scroll.direction, scroll.amount),
+            Some(over) => over,
+        };
+        let steps = path_steps(over, u128::from(scroll.amount));
+        let start = self.clock.now();
+        let mut previous = 0;
+        for step in 1..=steps {
+            let next = u32::try_from(u64::from(scroll.amount) * u64::from(step) / u64::from(steps))
+                .unwrap_or(u32::MAX);
+            self.schedule(
+                Pending::ScrollStep(scroll.direction, next - previous),
+                start + over * step / steps,
+            );
+            previous = next;
+        }
+        Ok(())
+    }
+}
+
+/// Number of steps to spread a movement of `distance` units over `over`: one
+/// step per [`PATH_STEP`], but no more steps than there are units to cover.
+fn path_steps(over: Duration, distance: u128) -> u32 {
+    u32::try_from((over.as_nanos() / PATH_STEP.as_nanos()).min(distance))
+        .unwrap_or(u32::MAX)
+        .max(1)
+}
+
+/// Check that `backend` can send every key, button and character of `input`,
+/// move the pointer to where it goes, and scroll if it does.
+fn check_input<B: InputBackend>(backend: &mut B, input: &InputType) -> Result<()> {
+    match *input {
+        InputType::Keyboard(ref key) => backend.check_key(key),
+        InputType::Mouse(click) => {
+            if let Some(Motion::To(..)) = click.at {
+                backend.check_move_to()?;
+            }
+            backend.check_button(click.button)
+        }
+        InputType::Drag(drag) => {
+            if let (Some(Motion::To(..)), _) | (_, Motion::To(..)) = (drag.from, drag.to) {
+                backend.check_move_to()?;
+            }
+            backend.check_button(drag.button)
+        }
+        InputType::Move(movement) => match movement.motion {
+            Motion::To(..) => backend.check_move_to(),
+            Motion::By(..) => Ok(()),
+        },
+        InputType::Type(ref text) => text.strokes().try_for_each(|stroke| match stroke {
+            Keystroke::Key(key) => backend.check_key(&key),
+            Keystroke::Unicode(c) => backend.check_unicode(c),
+        }),
+        InputType::Sequence(ref sequence) => sequence
+            .steps
+            .iter()
+            .filter_map(InputType::from_step)
+            .try_for_each(|input| check_input(backend, &input)),
+        InputType::Scroll(..) => backend.check_scroll(),
+    }
+}
+
+/// Settings for a run of [`process_events`].
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Time to wait before sending any input.
+    pub start_delay: Duration,
+    /// What to do with firings missed while falling behind.
+    pub catch_up: CatchUp,
+    /// What pauses input.
+    pub pause_source: PauseSource,
+    /// Seed for interval jitter, or `None` for a random seed.
+    pub seed: Option<u64>,
+    /// Stop after this many events have fired in total.
+    pub max_events: Option<u64>,
+    /// Stop after this much time has passed.
+    pub max_runtime: Option<Duration>,
+    /// How long to hold keys and buttons down, unless an event sets its own.
+    pub hold: Duration,
+}
+
+/// Check that `backend` can send every one of `eventspecs`, then queue them
+/// up and run them until a limit in `options` is reached or every event has
+/// been retired.
+pub fn process_events<B: InputBackend + std::fmt::Debug>(
+    mut backend: B,
+    eventspecs: Vec<EventSpec>,
+    options: &RunOptions,
+) -> Result<()> {
+    let inputevents = eventspecs
+        .into_iter()
+        .map(InputEvent::from)
+        .collect::<Vec<_>>();
+    // Catch anything the backend can't send before sending anything at all
+    for inputevent in &inputevents {
+        check_input(&mut backend, &inputevent.event)?;
+    }
+    let mut event_queue = InputEventQueue::new(backend);
+    event_queue.set_catch_up(options.catch_up);
+    event_queue.set_pause_source(options.pause_source);
+    if let Some(seed) = options.seed {
+        event_queue.set_seed(seed);
+    }
+    event_queue.set_max_events(options.max_events);
+    event_queue.set_max_runtime(options.max_runtime);
+    event_queue.set_default_hold(options.hold);
+    for inputevent in inputevents {
+        event_queue.add_event(inputevent);
+    }
+
+    debug!("All input events: {:?}", event_queue);
+    event_queue.start(options.start_delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+    use crate::eventspec::{DEFAULT_TYPE_DELAY, WHEEL_DETENT};
+
+    /// Records every emitted input with the virtual time it was emitted at.
+    struct Recorder {
+        clock: VirtualClock,
+        emitted: Vec<(u64, String)>,
+        emit_cost: Duration,
+        position: (i32, i32),
+        paused: bool,
+        can_move_to: bool,
+        scrolls_checked: usize,
+        /// Jump the clock by this much once this many inputs are recorded.
+        suspend: Option<(usize, Duration)>,
+    }
+
+    impl Recorder {
+        fn new(clock: &VirtualClock) -> Self {
+            Recorder {
+                clock: clock.clone(),
+                emitted: Vec::new(),
+                emit_cost: Duration::from_millis(0),
+                position: (0, 0),
+                paused: false,
+                can_move_to: true,
+                scrolls_checked: 0,
+                suspend: None,
+            }
+        }
+
+        fn record(&mut self, what: String) -> Result<()> {
+            self.emitted
+                .push((self.clock.elapsed().as_millis() as u64, what));
+            self.clock.advance(self.emit_cost);
+            if let Some((after, duration)) = self.suspend {
+                if self.emitted.len() == after {
+                    self.clock.advance(duration);
+                }
+            }
+            Ok(())
+        }
+
+        fn history(&self) -> Vec<(u64, &str)> {
+            self.emitted
+                .iter()
+                .map(|(t, what)| (*t, what.as_str()))
+                .collect()
+        }
+    }
+
+    impl InputBackend for Recorder {
+        fn key_down(&mut self, key: &str) -> Result<()> {
+            self.record(format!("{} down", key))
+        }
+
+        fn key_up(&mut self, key: &str) -> Result<()> {
+            self.record(format!("{} up", key))
+        }
+
+        fn button_down(&mut self, button: u8) -> Result<()> {
+            self.record(format!("{} down", button))
+        }
+
+        fn button_up(&mut self, button: u8) -> Result<()> {
+            self.record(format!("{} up", button))
+        }
+
+        fn emit_key(&mut self, key: &str) -> Result<()> {
+            self.record(key.to_owned())
+        }
+
+        fn emit_button(&mut self, button: u8) -> Result<()> {
+            self.record(button.to_string())
+        }
+
+        fn move_by(&mut self, dx: i32, dy: i32) -> Result<()> {
+            self.position = (self.position.0 + dx, self.position.1 + dy);
+            self.record(format!("by {:+},{:+}", dx, dy))
+        }
+
+        fn move_to(&mut self, x: i32, y: i32) -> Result<()> {
+            self.position = (x, y);
+            self.record(format!("to {},{}", x, y))
+        }
+
+        fn scroll(&mut self, direction: ScrollDirection, amount: u32) -> Result<()> {
+            self.record(format!("scroll {} {}", direction, amount))
+        }
+
+        fn type_unicode(&mut self, c: char) -> Result<()> {
+            self.record(format!("unicode {}", c))
+        }
+
+        fn pointer_position(&mut self) -> Option<(i32, i32)> {
+            Some(self.position)
+        }
+
+        fn check_move_to(&mut self) -> Result<()> {
+            match self.can_move_to {
+                true => Ok(()),
+                false => Err(Error::AbsoluteMoveUnavailable.into()),
+            }
+        }
+
+        fn check_scroll(&mut self) -> Result<()> {
+            self.scrolls_checked += 1;
+            Ok(())
+        }
+
+        fn paused(&self) -> bool {
+            self.paused
+        }
+    }
+
+    fn queue(
+        specs: Vec<EventSpec>,
+        clock: &VirtualClock,
+    ) -> InputEventQueue<Recorder, VirtualClock> {
+        let mut queue = InputEventQueue::with_clock(Recorder::new(clock), clock.clone());
+        for spec in specs {
+            queue.add_event(InputEvent::from(spec));
+        }
+        queue
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn every(millis: u64) -> Schedule {
+        Schedule::every(ms(millis))
+    }
+
+    /// A schedule that fires once, `millis` after the run starts.
+    fn once(millis: u64) -> Schedule {
+        Schedule {
+            count: Some(1),
+            ..every(millis)
+        }
+    }
+
+    fn key(name: &str, schedule: Schedule) -> EventSpec {
+        EventSpec::KeyboardEvent(name.to_owned(), schedule)
+    }
+
+    fn click(button: u8, schedule: Schedule) -> EventSpec {
+        EventSpec::MouseEvent(Click::button(button), schedule)
+    }
+
+    fn run(queue: &mut InputEventQueue<Recorder, VirtualClock>, count: usize) -> Vec<(u64, &str)> {
+        for _ in 0..count {
+            queue.run_next().unwrap();
+        }
+        queue.backend().history()
+    }
+
+    /// Start a run of `specs`, which must all retire, and return the backend
+    /// once it has ended.
+    fn run_once(specs: Vec<EventSpec>) -> Recorder {
+        let clock = VirtualClock::new();
+        let mut queue = queue(specs, &clock);
+        queue.start(Duration::from_millis(0)).unwrap();
+        assert!(queue.finished());
+        queue.backend
+    }
+
+    #[test]
+    fn mixed_intervals_fire_in_order() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(
+            vec![
+                key("a", every(300)),
+                click(1, every(700)),
+                key("b", every(300)),
+            ],
+            &clock,
+        );
+        assert_eq!(
+            run(&mut queue, 11),
+            vec![
+                (300, "a"),
+                (300, "b"),
+                (600, "a"),
+                (600, "b"),
+                (700, "1"),
+                (900, "a"),
+                (900, "b"),
+                (1200, "a"),
+                (1200, "b"),
+                (1400, "1"),
+                (1500, "a"),
+            ]
+        );
+        assert_eq!(clock.elapsed(), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn equal_intervals_keep_insertion_order() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(
+            vec![
+                key("c", every(250)),
+                key("a", every(250)),
+                key("b", every(250)),
+            ],
+            &clock,
+        );
+        assert_eq!(
+            run(&mut queue, 6),
+            vec![
+                (250, "c"),
+                (250, "a"),
+                (250, "b"),
+                (500, "c"),
+                (500, "a"),
+                (500, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn overdue_events_fire_immediately() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![key("a", every(100)), key("b", every(150))], &clock);
+        queue.backend.emit_cost = Duration::from_millis(120);
+        // Every emit runs past the next deadline, so each event fires as soon
+        // as the previous one finishes, but the schedule itself (a at 100,
+        // b at 150, a at 200, b and a at 300) doesn't move.
+        assert_eq!(
+            run(&mut queue, 5),
+            vec![(100, "a"), (220, "b"), (340, "a"), (460, "b"), (580, "a")]
+        );
+    }
+
+    #[test]
+    fn repeated_intervals_do_not_drift() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![key("a", every(333)), key("b", every(999))], &clock);
+        let emitted = run(&mut queue, 400);
+        // The 300th a and 100th b both land exactly on 99.9s; b was
+        // rescheduled first, so it fires first.
+        assert_eq!(emitted[398], (99900, "b"));
+        assert_eq!(emitted[399], (99900, "a"));
+        assert_eq!(emitted.iter().filter(|(_, what)| *what == "b").count(), 100);
+    }
+
+    #[test]
+    fn large_schedules_fire_in_deadline_order() {
+        let clock = VirtualClock::new();
+        let specs = (1..=300)
+            .rev()
+            .map(|i| key(&format!("k{}", i), every(i)))
+            .collect();
+        let mut queue = queue(specs, &clock);
+        let emitted = run(&mut queue, 300);
+        assert!(emitted.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        assert_eq!(emitted[0], (1, "k1"));
+        // k2 was queued before k1 was rescheduled
+        assert_eq!(emitted[1], (2, "k2"));
+        assert_eq!(emitted[2], (2, "k1"));
+    }
+
+    #[test]
+    fn postpone_shifts_every_deadline() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![key("a", every(100)), key("b", every(150))], &clock);
+        queue.postpone(Duration::from_millis(1000));
+        assert_eq!(
+            run(&mut queue, 3),
+            vec![(1100, "a"), (1150, "b"), (1200, "a")]
+        );
+    }
+
+    /// Fire "a:100" once, stall for just over a second, then fire 3 more.
+    fn stall(catch_up: CatchUp) -> Vec<(u64, String)> {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![key("a", every(100))], &clock);
+        queue.set_catch_up(catch_up);
+        queue.run_next().unwrap();
+        clock.advance(Duration::from_millis(1050));
+        for _ in 0..3 {
+            queue.run_next().unwrap();
+        }
+        queue.backend.emitted.clone()
+    }
+
+    #[test]
+    fn burst_fires_every_missed_slot() {
+        let times: Vec<u64> = stall(CatchUp::Burst).iter().map(|(t, _)| *t).collect();
+        assert_eq!(times, vec![100, 1150, 1150, 1150]);
+    }
+
+    #[test]
+    fn skip_drops_missed_slots() {
+        let times: Vec<u64> = stall(CatchUp::Skip).iter().map(|(t, _)| *t).collect();
+        // The first run after the stall only realigns to the 1200 slot
+        assert_eq!(times, vec![100, 1200, 1300]);
+    }
+
+    #[test]
+    fn coalesce_fires_once_for_missed_slots() {
+        let times: Vec<u64> = stall(CatchUp::Coalesce).iter().map(|(t, _)| *t).collect();
+        assert_eq!(times, vec![100, 1150, 1200, 1300]);
+    }
+
+    #[test]
+    fn events_catch_up_by_their_own_policy() {
+        let clock = VirtualClock::new();
+        let policy = |catch_up| Schedule {
+            catch_up: Some(catch_up),
+            ..every(100)
+        };
+        let mut queue = queue(
+            vec![
+                key("a", policy(CatchUp::Skip)),
+                key("b", policy(CatchUp::Coalesce)),
+            ],
+            &clock,
+        );
+        run(&mut queue, 2);
+        clock.advance(Duration::from_millis(1050));
+        // The queue's default would burst, but a skips to the 1200 slot and
+        // b fires once on the way there
+        assert_eq!(
+            run(&mut queue, 4),
+            vec![
+                (100, "a"),
+                (100, "b"),
+                (1150, "b"),
+                (1200, "a"),
+                (1200, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn each_policy_catches_up_after_a_suspend() {
+        let suspended = |catch_up| {
+            let clock = VirtualClock::new();
+            let mut queue = queue(vec![key("a", every(100))], &clock);
+            queue.set_catch_up(catch_up);
+            queue.backend.suspend = Some((1, Duration::from_millis(1050)));
+            queue.set_max_runtime(Some(Duration::from_millis(1350)));
+            queue.start(Duration::from_millis(0)).unwrap();
+            let emitted = queue.backend.emitted.clone();
+            emitted.into_iter().map(|(t, _)| t).collect::<Vec<_>>()
+        };
+        // Every slot from 200 to 1100 fires on waking at 1150
+        let mut burst = vec![100];
+        burst.extend(std::iter::repeat_n(1150, 10));
+        burst.extend([1200, 1300]);
+        assert_eq!(suspended(CatchUp::Burst), burst);
+        assert_eq!(suspended(CatchUp::Skip), vec![100, 1200, 1300]);
+        assert_eq!(suspended(CatchUp::Coalesce), vec![100, 1150, 1200, 1300]);
+    }
+
+    #[test]
+    fn late_events_still_fire_when_skipping() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![key("a", every(100)), key("b", every(150))], &clock);
+        queue.set_catch_up(CatchUp::Skip);
+        queue.backend.emit_cost = Duration::from_millis(30);
+        assert_eq!(
+            run(&mut queue, 4),
+            vec![(100, "a"), (150, "b"), (200, "a"), (300, "b")]
+        );
+    }
+
+    fn jittered(interval: u64, jitter: Jitter, seed: u64) -> Vec<u64> {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![], &clock);
+        // Seeded first, since the first deadline is already jittered
+        queue.set_seed(seed);
+        let schedule = Schedule {
+            jitter: Some(jitter),
+            ..every(interval)
+        };
+        queue.add_event(InputEvent::from(key("a", schedule)));
+        let emitted = run(&mut queue, 200);
+        emitted
+            .windows(2)
+            .map(|pair| pair[1].0 - pair[0].0)
+            .collect()
+    }
+
+    #[test]
+    fn uniform_jitter_stays_in_range() {
+        let intervals = jittered(1000, Jitter::Uniform(ms(200)), 1);
+        assert!(intervals.iter().all(|i| (800..=1200).contains(i)));
+        assert!(intervals.iter().any(|i| *i != 1000));
+    }
+
+    #[test]
+    fn gaussian_jitter_is_centered() {
+        let intervals = jittered(1000, Jitter::Gaussian(ms(100)), 2);
+        let mean = intervals.iter().sum::<u64>() / intervals.len() as u64;
+        assert!((950..=1050).contains(&mean), "mean interval {}", mean);
+        assert!(intervals.iter().any(|i| *i != 1000));
+    }
+
+    #[test]
+    fn seeded_jitter_is_reproducible() {
+        let gaussian = || Jitter::Gaussian(ms(100));
+        assert_eq!(jittered(500, gaussian(), 3), jittered(500, gaussian(), 3));
+        let uniform = || Jitter::Uniform(ms(100));
+        assert_ne!(jittered(500, uniform(), 3), jittered(500, uniform(), 4));
+    }
+
+    #[test]
+    fn counted_events_retire() {
+        let clock = VirtualClock::new();
+        let counted = Schedule {
+            count: Some(2),
+            ..every(100)
+        };
+        let mut queue = queue(vec![key("a", counted), key("b", every(250))], &clock);
+        assert_eq!(
+            run(&mut queue, 4),
+            vec![(100, "a"), (200, "a"), (250, "b"), (500, "b")]
+        );
+    }
+
+    #[test]
+    fn start_stops_when_every_event_retires() {
+        let clock = VirtualClock::new();
+        let counted = Schedule {
+            count: Some(3),
+            ..every(100)
+        };
+        let mut queue = queue(vec![key("a", counted), click(1, once(150))], &clock);
+        queue.start(Duration::from_millis(50)).unwrap();
+        assert_eq!(
+            run(&mut queue, 0),
+            vec![(100, "a"), (150, "1"), (200, "a"), (300, "a")]
+        );
+        assert!(queue.finished());
+    }
+
+    #[test]
+    fn start_stops_at_max_events() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![key("a", every(100)), key("b", every(150))], &clock);
+        queue.set_max_events(Some(5));
+        queue.start(Duration::from_millis(0)).unwrap();
+        assert_eq!(queue.fired(), 5);
+        assert_eq!(clock.elapsed(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn start_stops_at_max_runtime() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![key("a", every(300))], &clock);
+        queue.set_max_runtime(Some(Duration::from_millis(1000)));
+        queue.start(Duration::from_millis(0)).unwrap();
+        assert_eq!(queue.fired(), 3);
+        assert_eq!(clock.elapsed(), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn pause_source_none_ignores_numlock() {
+        let paused_run = |pause_source| {
+            let clock = VirtualClock::new();
+            let mut queue = queue(vec![key("a", every(300))], &clock);
+            queue.backend.paused = true;
+            queue.set_pause_source(pause_source);
+            queue.set_max_runtime(Some(Duration::from_millis(1000)));
+            queue.start(Duration::from_millis(0)).unwrap();
+            queue.fired()
+        };
+        assert_eq!(paused_run(PauseSource::NumLock), 0);
+        assert_eq!(paused_run(PauseSource::None), 3);
+    }
+
+    #[test]
+    fn holds_do_not_block_other_events() {
+        let clock = VirtualClock::new();
+        let held = Schedule {
+            hold: Some(ms(150)),
+            ..every(200)
+        };
+        let mut queue = queue(vec![key("a", held), key("b", every(100))], &clock);
+        assert_eq!(
+            run(&mut queue, 5),
+            vec![
+                (100, "b"),
+                (200, "a down"),
+                (200, "b"),
+                (300, "b"),
+                (350, "a up"),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_hold_applies_to_every_event() {
+        let clock = VirtualClock::new();
+        let counted = Schedule {
+            count: Some(2),
+            ..every(100)
+        };
+        let held = Schedule {
+            hold: Some(ms(10)),
+            ..once(150)
+        };
+        let mut queue = queue(vec![click(1, counted), key("a", held)], &clock);
+        queue.set_default_hold(Duration::from_millis(50));
+        queue.start(Duration::from_millis(0)).unwrap();
+        assert_eq!(
+            run(&mut queue, 0),
+            vec![
+                (100, "1 down"),
+                (150, "a down"),
+                (150, "1 up"),
+                (160, "a up"),
+                (200, "1 down"),
+                (250, "1 up"),
+            ]
+        );
+    }
+
+    #[test]
+    fn held_keys_are_released_when_the_run_ends() {
+        let held = Schedule {
+            hold: Some(ms(1000)),
+            ..once(100)
+        };
+        assert_eq!(
+            run_once(vec![key("a", held)]).history(),
+            vec![(100, "a down"), (1100, "a up")]
+        );
+    }
+
+    #[test]
+    fn max_events_releases_held_keys_at_once() {
+        let clock = VirtualClock::new();
+        let held = Schedule {
+            hold: Some(ms(1000)),
+            ..every(100)
+        };
+        let mut queue = queue(vec![key("a", held)], &clock);
+        queue.set_max_events(Some(1));
+        queue.start(Duration::from_millis(0)).unwrap();
+        assert_eq!(run(&mut queue, 0), vec![(100, "a down"), (100, "a up")]);
+    }
+
+    #[test]
+    fn max_runtime_releases_held_keys_at_once() {
+        let clock = VirtualClock::new();
+        let held = Schedule {
+            hold: Some(ms(1000)),
+            ..every(300)
+        };
+        let mut queue = queue(vec![key("a", held)], &clock);
+        queue.set_max_runtime(Some(Duration::from_millis(500)));
+        queue.start(Duration::from_millis(0)).unwrap();
+        assert_eq!(run(&mut queue, 0), vec![(300, "a down"), (500, "a up")]);
+    }
+
+    #[test]
+    fn moves_to_positions_are_checked_before_the_run() {
+        let clock = VirtualClock::new();
+        let mut recorder = Recorder::new(&clock);
+        recorder.can_move_to = false;
+        let mut check =
+            |spec: EventSpec| check_input(&mut recorder, &InputEvent::from(spec).event).is_ok();
+        let jump = |motion| Move { motion, over: None };
+        let at = |motion| Click {
+            button: 1,
+            at: Some(motion),
+        };
+        assert!(check(EventSpec::MoveEvent(
+            jump(Motion::By(5, 5)),
+            every(1000)
+        )));
+        assert!(!check(EventSpec::MoveEvent(
+            jump(Motion::To(5, 5)),
+            every(1000)
+        )));
+        assert!(check(click(1, every(1000))));
+        assert!(check(EventSpec::MouseEvent(
+            at(Motion::By(5, 5)),
+            every(1000)
+        )));
+        assert!(!check(EventSpec::MouseEvent(
+            at(Motion::To(5, 5)),
+            every(1000)
+        )));
+        let drag = Drag {
+            button: 1,
+            from: None,
+            to: Motion::To(5, 5),
+            over: None,
+        };
+        assert!(!check(EventSpec::DragEvent(drag, every(1000))));
+    }
+
+    #[test]
+    fn scrolls_are_checked_before_the_run() {
+        let clock = VirtualClock::new();
+        let mut recorder = Recorder::new(&clock);
+        let scroll = Scroll {
+            direction: ScrollDirection::Down,
+            amount: 120,
+            over: None,
+        };
+        let steps = vec![Step::Key("a".to_owned()), Step::Scroll(scroll)];
+        for spec in [
+            key("a", every(1000)),
+            EventSpec::ScrollEvent(scroll, every(1000)),
+            EventSpec::SequenceEvent(Sequence { steps }, every(1000)),
+        ] {
+            check_input(&mut recorder, &InputEvent::from(spec).event).unwrap();
+        }
+        assert_eq!(recorder.scrolls_checked, 2);
+    }
+
+    #[test]
+    fn clicks_move_the_pointer_first() {
+        let clock = VirtualClock::new();
+        let at = Click {
+            button: 1,
+            at: Some(Motion::To(640, 480)),
+        };
+        let mut queue = queue(
+            vec![EventSpec::MouseEvent(at, every(100)), click(3, every(150))],
+            &clock,
+        );
+        assert_eq!(
+            run(&mut queue, 3),
+            vec![
+                (100, "to 640,480"),
+                (100, "1"),
+                (150, "3"),
+                (200, "to 640,480"),
+                (200, "1")
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_paths_are_spread_over_time() {
+        let clock = VirtualClock::new();
+        let path = Move {
+            motion: Motion::By(30, -6),
+            over: Some(ms(30)),
+        };
+        let mut queue = queue(vec![EventSpec::MoveEvent(path, every(1000))], &clock);
+        assert_eq!(
+            run(&mut queue, 4),
+            vec![
+                (1010, "by +10,-2"),
+                (1020, "by +10,-2"),
+                (1030, "by +10,-2")
+            ]
+        );
+        assert_eq!(queue.backend().position, (30, -6));
+    }
+
+    #[test]
+    fn paths_do_not_block_and_finish_when_the_run_ends() {
+        let path = Move {
+            motion: Motion::To(100, 50),
+            over: Some(ms(50)),
+        };
+        assert_eq!(
+            run_once(vec![
+                EventSpec::MoveEvent(path, once(1000)),
+                key("a", once(1020))
+            ])
+            .history(),
+            vec![
+                (1010, "to 20,10"),
+                (1020, "a"),
+                (1020, "to 40,20"),
+                (1030, "to 60,30"),
+                (1040, "to 80,40"),
+                (1050, "to 100,50"),
+            ]
+        );
+    }
+
+    #[test]
+    fn gradual_scrolls_add_up_to_the_amount() {
+        let clock = VirtualClock::new();
+        let scroll = |direction, amount, over| Scroll {
+            direction,
+            amount,
+            over,
+        };
+        let mut queue = queue(
+            vec![
+                EventSpec::ScrollEvent(
+                    scroll(ScrollDirection::Down, WHEEL_DETENT, Some(ms(30))),
+                    every(1000),
+                ),
+                EventSpec::ScrollEvent(
+                    scroll(ScrollDirection::Left, 2 * WHEEL_DETENT, None),
+                    every(1000),
+                ),
+            ],
+            &clock,
+        );
+        assert_eq!(
+            run(&mut queue, 5),
+            vec![
+                (1000, "scroll left 240"),
+                (1010, "scroll down 40"),
+                (1020, "scroll down 40"),
+                (1030, "scroll down 40"),
+            ]
+        );
+    }
+
+    #[test]
+    fn bursts_are_not_interleaved() {
+        let clock = VirtualClock::new();
+        let burst = Schedule {
+            repeat: 3,
+            gap: ms(60),
+            ..once(100)
+        };
+        let mut queue = queue(vec![click(1, burst), key("a", once(130))], &clock);
+        queue.start(Duration::from_millis(0)).unwrap();
+        assert_eq!(
+            run(&mut queue, 0),
+            vec![(100, "1"), (160, "1"), (220, "1"), (220, "a")]
+        );
+        assert_eq!(queue.fired(), 2);
+    }
+
+    #[test]
+    fn bursts_hold_each_repetition() {
+        let clock = VirtualClock::new();
+        let burst = Schedule {
+            hold: Some(ms(10)),
+            repeat: 2,
+            gap: ms(20),
+            ..every(100)
+        };
+        let mut queue = queue(vec![key("a", burst), key("b", every(150))], &clock);
+        assert_eq!(
+            run(&mut queue, 3),
+            vec![
+                (100, "a down"),
+                (110, "a up"),
+                (130, "a down"),
+                (140, "a up"),
+                (150, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn drags_release_after_the_path() {
+        let drag = Drag {
+            button: 1,
+            from: Some(Motion::To(5, 5)),
+            to: Motion::To(35, 5),
+            over: Some(ms(30)),
+        };
+        assert_eq!(
+            run_once(vec![EventSpec::DragEvent(drag, once(1000))]).history(),
+            vec![
+                (1000, "to 5,5"),
+                (1000, "1 down"),
+                (1010, "to 15,5"),
+                (1020, "to 25,5"),
+                (1030, "to 35,5"),
+                (1030, "1 up"),
+            ]
+        );
+    }
+
+    #[test]
+    fn interrupted_drags_release_the_button() {
+        let clock = VirtualClock::new();
+        let drag = Drag {
+            button: 3,
+            from: None,
+            to: Motion::By(100, 0),
+            over: Some(ms(1000)),
+        };
+        let mut queue = queue(vec![EventSpec::DragEvent(drag, every(1000))], &clock);
+        run(&mut queue, 2);
+        queue.release_held(false).unwrap();
+        assert_eq!(
+            run(&mut queue, 0),
+            vec![(1000, "3 down"), (1010, "by +1,+0"), (1010, "3 up")]
+        );
+    }
+
+    #[test]
+    fn text_is_typed_without_interruption() {
+        let text = Text {
+            text: "Hé!\n".to_owned(),
+            delay: ms(20),
+        };
+        assert_eq!(
+            run_once(vec![
+                EventSpec::TypeEvent(text, once(100)),
+                key("b", once(120))
+            ])
+            .history(),
+            vec![
+                (100, "H"),
+                (120, "unicode é"),
+                (140, "!"),
+                (160, "Return"),
+                (160, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sequences_are_not_interleaved() {
+        let clock = VirtualClock::new();
+        let steps = vec![
+            Step::Key("ctrl+a".to_owned()),
+            Step::Wait(ms(50)),
+            Step::Key("ctrl+c".to_owned()),
+            Step::Wait(ms(200)),
+            Step::Key("alt+Tab".to_owned()),
+            Step::Key("ctrl+v".to_owned()),
+        ];
+        let held = Schedule {
+            hold: Some(ms(10)),
+            ..every(1000)
+        };
+        let mut queue = queue(
+            vec![
+                EventSpec::SequenceEvent(Sequence { steps }, held),
+                key("b", every(1100)),
+            ],
+            &clock,
+        );
+        assert_eq!(
+            run(&mut queue, 2),
+            vec![
+                (1000, "ctrl+a down"),
+                (1010, "ctrl+a up"),
+                (1060, "ctrl+c down"),
+                (1070, "ctrl+c up"),
+                (1270, "alt+Tab down"),
+                (1280, "alt+Tab up"),
+                (1280, "ctrl+v down"),
+                (1290, "ctrl+v up"),
+                (1290, "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sequence_steps_finish_before_the_next() {
+        let steps = vec![
+            Step::Move(Move {
+                motion: Motion::To(30, 0),
+                over: Some(ms(20)),
+            }),
+            Step::Click(Click {
+                button: 1,
+                at: Some(Motion::To(5, 5)),
+            }),
+            Step::Type(Text {
+                text: "hi".to_owned(),
+                delay: DEFAULT_TYPE_DELAY,
+            }),
+        ];
+        assert_eq!(
+            run_once(vec![EventSpec::SequenceEvent(
+                Sequence { steps },
+                once(1000)
+            )])
+            .history(),
+            vec![
+                (1010, "to 15,0"),
+                (1020, "to 30,0"),
+                (1020, "to 5,5"),
+                (1020, "1"),
+                (1020, "h"),
+                (1050, "i"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_queue_idles() {
+        let clock = VirtualClock::new();
+        let mut queue = queue(vec![], &clock);
+        assert!(run(&mut queue, 3).is_empty());
+        assert_eq!(clock.elapsed(), Duration::from_millis(300));
+    }
+}
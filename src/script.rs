@@ -0,0 +1,302 @@
+//! Scripts describing events in a file instead of on the command line, run
+//! with `clickrs run FILE`.
+//!
+//! A script has one statement per line, and `#` starts a comment:
+//!
+//! ```text
+//! # Press a twice a second, and click every 2 seconds
+//! every 500ms press a
+//! every 2s~500ms,count=10 click left at 100,200
+//!
+//! every 1m sequence
+//!     ctrl+a
+//!     wait 50ms
+//!     repeat 3
+//!         Down
+//!         wait 20ms
+//!     end
+//!     type "done\n"
+//! end
+//! ```
+//!
+//! `every SCHEDULE STEP` fires a single step on a schedule written as it is
+//! on the command line, and `every SCHEDULE sequence` fires the steps up to
+//! the matching `end` as one [`Sequence`]. Steps are written as for
+//! [`Step::parse`], one to a line, and `repeat N` ... `end` repeats the steps
+//! inside it `N` times.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use log::debug;
+
+use crate::errors::Error;
+use crate::eventspec::{parse_schedule, EventSpec, Schedule, Sequence, Step};
+
+/// A line of a script, with its comment removed.
+struct Line<'a> {
+    number: usize,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// The error `message` at `part` of the line, which must be a slice of
+    /// it.
+    fn error(&self, source: &str, part: &str, message: String) -> Error {
+        let offset = (part.as_ptr() as usize).saturating_sub(self.text.as_ptr() as usize);
+        let column = self.text[..offset.min(self.text.len())].chars().count() + 1;
+        Error::Script(source.to_owned(), self.number, column, message)
+    }
+}
+
+/// Remove a `#` comment from the end of `line`, unless the `#` is quoted.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Split off the first word of `text`, returning it and the rest.
+fn first_word(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    text.split_once(char::is_whitespace)
+        .map_or((text, ""), |(word, rest)| (word, rest.trim_start()))
+}
+
+/// The event that fires `step` on `schedule`, or `None` for a wait, which
+/// only makes sense in a sequence.
+fn step_event(step: Step, schedule: Schedule) -> Option<EventSpec> {
+    match step {
+        Step::Key(key) => Some(EventSpec::KeyboardEvent(key, schedule)),
+        Step::Click(click) => Some(EventSpec::MouseEvent(click, schedule)),
+        Step::Move(movement) => Some(EventSpec::MoveEvent(movement, schedule)),
+        Step::Scroll(scroll) => Some(EventSpec::ScrollEvent(scroll, schedule)),
+        Step::Drag(drag) => Some(EventSpec::DragEvent(drag, schedule)),
+        Step::Type(text) => Some(EventSpec::TypeEvent(text, schedule)),
+        Step::Wait(_) => None,
+    }
+}
+
+/// Read the script at `path`.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<EventSpec>> {
+    let path = path.as_ref();
+    let script = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read script {:?}", path))?;
+    parse(&path.display().to_string(), &script)
+}
+
+/// Parse `script`, naming it `source` in errors.
+pub fn parse(source: &str, script: &str) -> Result<Vec<EventSpec>> {
+    let mut lines = script.lines().enumerate().map(|(i, text)| Line {
+        number: i + 1,
+        text: strip_comment(text),
+    });
+    let mut eventspecs = Vec::new();
+    while let Some(line) = lines.next() {
+        let (word, rest) = first_word(line.text);
+        match word {
+            "" => continue,
+            "every" => {
+                let (schedule_str, step_str) = first_word(rest);
+                if step_str.is_empty() {
+                    return Err(line
+                        .error(
+                            source,
+                            word,
+                            "expected every INTERVAL STEP or every INTERVAL sequence".to_owned(),
+                        )
+                        .into());
+                }
+                let (schedule, _) = parse_schedule(schedule_str, &[])
+                    .map_err(|e| line.error(source, schedule_str, e.to_string()))?;
+                let eventspec = if step_str == "sequence" {
+                    let steps = parse_block(source, &mut lines, &line, step_str)?;
+                    EventSpec::SequenceEvent(Sequence { steps }, schedule)
+                } else {
+                    let step = Step::parse(step_str)
+                        .map_err(|e| line.error(source, step_str, e.to_string()))?;
+                    step_event(step, schedule).ok_or_else(|| {
+                        line.error(
+                            source,
+                            step_str,
+                            "wait only makes sense in a sequence".to_owned(),
+                        )
+                    })?
+                };
+                debug!("Line {}: {:?}", line.number, eventspec);
+                eventspecs.push(eventspec);
+            }
+            "end" => {
+                return Err(line
+                    .error(source, word, "end without a sequence or repeat".to_owned())
+                    .into())
+            }
+            "wait" | "repeat" => {
+                return Err(line
+                    .error(
+                        source,
+                        word,
+                        format!("{} only makes sense in a sequence", word),
+                    )
+                    .into())
+            }
+            _ => {
+                return Err(line
+                    .error(source, word, format!("expected every, but found {}", word))
+                    .into())
+            }
+        }
+    }
+    Ok(eventspecs)
+}
+
+/// Parse the steps of a block opened by `opener` on `opened`, up to its
+/// `end`, unrolling any `repeat` blocks inside it.
+fn parse_block<'a, I: Iterator<Item = Line<'a>>>(
+    source: &str,
+    lines: &mut I,
+    opened: &Line<'a>,
+    opener: &str,
+) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    while let Some(line) = lines.next() {
+        let (word, rest) = first_word(line.text);
+        match word {
+            "" => continue,
+            "end" if rest.is_empty() => return Ok(steps),
+            "repeat" => {
+                let count = rest
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .ok_or_else(|| {
+                        line.error(
+                            source,
+                            rest,
+                            "expected repeat N, where N is a positive integer".to_owned(),
+                        )
+                    })?;
+                let repeated = parse_block(source, lines, &line, word)?;
+                for _ in 0..count {
+                    steps.extend(repeated.iter().cloned());
+                }
+            }
+            "every" | "sequence" => {
+                return Err(line
+                    .error(
+                        source,
+                        word,
+                        format!("{} can't be inside a {}", word, opener),
+                    )
+                    .into())
+            }
+            _ => {
+                let step_str = line.text.trim();
+                steps.push(
+                    Step::parse(step_str)
+                        .map_err(|e| line.error(source, step_str, e.to_string()))?,
+                );
+            }
+        }
+    }
+    Err(opened
+        .error(
+            source,
+            opener,
+            format!("{} is never closed with end", opener),
+        )
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eventspec::{Click, Motion, Text, DEFAULT_TYPE_DELAY};
+    use std::time::Duration;
+
+    const SCRIPT: &str = r##"
+# Press a twice a second, and click every 2 seconds
+every 500ms press a
+every 2s~500ms,count=10 click left at 100,200   # top left
+
+every 1m sequence
+    ctrl+a
+    wait 50ms
+    repeat 2
+        Down
+        wait 20ms
+    end
+    type "# done\n"
+end
+"##;
+
+    #[test]
+    fn scripts_compile_to_events() {
+        let eventspecs = parse("test.clk", SCRIPT).unwrap();
+        assert_eq!(eventspecs.len(), 3);
+        assert_eq!(
+            eventspecs[0],
+            EventSpec::KeyboardEvent("a".to_owned(), Schedule::every(Duration::from_millis(500)))
+        );
+        match eventspecs[1] {
+            EventSpec::MouseEvent(click, ref schedule) => {
+                assert_eq!(
+                    click,
+                    Click {
+                        button: 1,
+                        at: Some(Motion::To(100, 200))
+                    }
+                );
+                assert_eq!(schedule.count, Some(10));
+            }
+            ref other => panic!("{:?}", other),
+        }
+        let key = |name: &str| Step::Key(name.to_owned());
+        let wait = |ms| Step::Wait(Duration::from_millis(ms));
+        assert_eq!(
+            eventspecs[2],
+            EventSpec::SequenceEvent(
+                Sequence {
+                    steps: vec![
+                        key("ctrl+a"),
+                        wait(50),
+                        key("Down"),
+                        wait(20),
+                        key("Down"),
+                        wait(20),
+                        Step::Type(Text {
+                            text: "# done\n".to_owned(),
+                            delay: DEFAULT_TYPE_DELAY,
+                        }),
+                    ]
+                },
+                Schedule::every(Duration::from_secs(60))
+            )
+        );
+    }
+
+    #[test]
+    fn errors_give_the_line_and_column() {
+        let error = |script: &str| parse("test.clk", script).unwrap_err().to_string();
+        assert!(error("every 1s press a\n  every 2x press b").starts_with("test.clk:2:9: "));
+        assert!(error("every 1s\n").starts_with("test.clk:1:1: "));
+        assert!(error("every 1s clack 1").starts_with("test.clk:1:10: "));
+        assert!(error("\n\nevery 1s sequence\n  a\n  repeat 0\n  end\nend")
+            .starts_with("test.clk:5:10: "));
+        assert!(error("every 1s sequence\n  a\n  hyperr+a\nend").starts_with("test.clk:3:3: "));
+        assert!(error("# only a comment\nevery 1s sequence\n  a\n")
+            .starts_with("test.clk:2:10: sequence is never closed"));
+        assert!(error("end").starts_with("test.clk:1:1: end without"));
+        assert!(error("wait 1s").starts_with("test.clk:1:1: wait only"));
+        assert!(error("every 1s wait 1s").starts_with("test.clk:1:10: wait only"));
+    }
+}
@@ -1,14 +1,23 @@
-use std::collections::VecDeque;
-use std::time::{Duration, Instant};
-
-use crate::EventSpec;
+use crate::backend::{InputBackend, ScreenSize, UnicodeInput};
+use crate::buttons;
+use crate::chord::{merge_modifiers, Chord, Modifier};
+use crate::errors::Error;
+use crate::eventspec::{ScrollDirection, WHEEL_DETENT};
+use crate::keys;
+use crate::layout::{self, Layout, LayoutKey};
 use anyhow::Result;
-use log::{debug, info};
+use log::debug;
+use std::convert::TryFrom;
+use std::os::raw::c_int;
 use uinput::event::controller::Controller::Mouse;
-use uinput::event::controller::Mouse::{Extra, Left, Middle, Right};
-use uinput::event::keyboard::Key;
+use uinput::event::controller::Mouse as MouseButton;
+use uinput::event::keyboard::{
+    Attendant, Braille, Camera, Function, InputAssist, Key, KeyPad, Keyboard, Misc, Numeric,
+    TouchPad,
+};
 use uinput::event::relative::Position::{X, Y};
 use uinput::event::relative::Relative::Position;
+use uinput::event::Code;
 use uinput::event::Event::{Controller, Relative};
 
 struct NumlockWatcher {
@@ -68,368 +77,504 @@ impl NumlockWatcher {
 #[derive(Debug, Clone)]
 pub struct ModifiedEvent {
     pub event: uinput::Event,
-    shift: bool,
-    control: bool,
-    alt: bool,
+    /// Modifier keys to hold down around the event, outermost first.
+    modifiers: Vec<Key>,
 }
 
 impl std::fmt::Display for ModifiedEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut chain: bool = false;
-        if self.shift {
-            if chain {
-                write!(f, " + ")?;
-            }
-            write!(f, "<SHIFT>")?;
-            chain = true;
-        }
-        if self.control {
-            if chain {
-                write!(f, " + ")?;
-            }
-            write!(f, "<CONTROL>")?;
-            chain = true;
-        }
-        if self.alt {
-            if chain {
-                write!(f, " + ")?;
-            }
-            write!(f, "<ALT>")?;
-            chain = true;
-        }
-
-        if chain {
-            write!(f, " + ")?;
+        for modifier in &self.modifiers {
+            write!(f, "<{:?}> + ", modifier)?;
         }
         write!(f, "{:?}", self.event)
     }
 }
 
-impl From<u8> for ModifiedEvent {
-    fn from(button: u8) -> Self {
-        let event = match button {
-            // I can't remember whether the x11 code started counting from 0 or 1
-            0 => Left.into(),
-            1 => Left.into(),
-            2 => Middle.into(),
-            3 => Right.into(),
-            // TODO: we should error here
-            _ => Extra.into(),
-        };
-        ModifiedEvent {
-            event,
-            shift: false,
-            alt: false,
-            control: false,
-        }
+fn modifier_key(modifier: Modifier) -> Key {
+    match modifier {
+        Modifier::LeftShift => Key::LeftShift,
+        Modifier::RightShift => Key::RightShift,
+        Modifier::LeftControl => Key::LeftControl,
+        Modifier::RightControl => Key::RightControl,
+        Modifier::LeftAlt => Key::LeftAlt,
+        Modifier::RightAlt => Key::RightAlt,
+        Modifier::LeftSuper => Key::LeftMeta,
+        Modifier::RightSuper => Key::RightMeta,
+        // The kernel has no separate AltGr key; layouts put it on right alt
+        Modifier::AltGr => Key::RightAlt,
     }
 }
 
-impl From<&str> for ModifiedEvent {
-    fn from(key: &str) -> Self {
-        // See https://github.com/meh/rust-uinput
-        let (event, shift) = match key {
-            "1" => (Key::_1.into(), false),
-            "2" => (Key::_2.into(), false),
-            "3" => (Key::_3.into(), false),
-            "4" => (Key::_4.into(), false),
-            "5" => (Key::_5.into(), false),
-            "6" => (Key::_6.into(), false),
-            "7" => (Key::_7.into(), false),
-            "8" => (Key::_8.into(), false),
-            "9" => (Key::_9.into(), false),
-            "0" => (Key::_0.into(), false),
-            "!" => (Key::_1.into(), true),
-            "@" => (Key::_2.into(), true),
-            "#" => (Key::_3.into(), true),
-            "$" => (Key::_4.into(), true),
-            "%" => (Key::_5.into(), true),
-            "^" => (Key::_6.into(), true),
-            "&" => (Key::_7.into(), true),
-            "*" => (Key::_8.into(), true),
-            "(" => (Key::_9.into(), true),
-            ")" => (Key::_0.into(), true),
-            "a" => (Key::A.into(), false),
-            "b" => (Key::B.into(), false),
-            "c" => (Key::C.into(), false),
-            "d" => (Key::D.into(), false),
-            "e" => (Key::E.into(), false),
-            "f" => (Key::F.into(), false),
-            "g" => (Key::G.into(), false),
-            "h" => (Key::H.into(), false),
-            "i" => (Key::I.into(), false),
-            "j" => (Key::J.into(), false),
-            "k" => (Key::K.into(), false),
-            "l" => (Key::L.into(), false),
-            "m" => (Key::M.into(), false),
-            "n" => (Key::N.into(), false),
-            "o" => (Key::O.into(), false),
-            "p" => (Key::P.into(), false),
-            "q" => (Key::Q.into(), false),
-            "r" => (Key::R.into(), false),
-            "s" => (Key::S.into(), false),
-            "t" => (Key::T.into(), false),
-            "u" => (Key::U.into(), false),
-            "v" => (Key::V.into(), false),
-            "w" => (Key::W.into(), false),
-            "x" => (Key::X.into(), false),
-            "y" => (Key::Y.into(), false),
-            "z" => (Key::Z.into(), false),
-            "A" => (Key::A.into(), true),
-            "B" => (Key::B.into(), true),
-            "C" => (Key::C.into(), true),
-            "D" => (Key::D.into(), true),
-            "E" => (Key::E.into(), true),
-            "F" => (Key::F.into(), true),
-            "G" => (Key::G.into(), true),
-            "H" => (Key::H.into(), true),
-            "I" => (Key::I.into(), true),
-            "J" => (Key::J.into(), true),
-            "K" => (Key::K.into(), true),
-            "L" => (Key::L.into(), true),
-            "M" => (Key::M.into(), true),
-            "N" => (Key::N.into(), true),
-            "O" => (Key::O.into(), true),
-            "P" => (Key::P.into(), true),
-            "Q" => (Key::Q.into(), true),
-            "R" => (Key::R.into(), true),
-            "S" => (Key::S.into(), true),
-            "T" => (Key::T.into(), true),
-            "U" => (Key::U.into(), true),
-            "V" => (Key::V.into(), true),
-            "W" => (Key::W.into(), true),
-            "X" => (Key::X.into(), true),
-            "Y" => (Key::Y.into(), true),
-            "Z" => (Key::Z.into(), true),
-            "," => (Key::Comma.into(), false),
-            "." => (Key::Dot.into(), false),
-            "/" => (Key::Slash.into(), false),
-            "<" => (Key::Comma.into(), true),
-            ">" => (Key::Dot.into(), true),
-            "?" => (Key::Slash.into(), true),
-            " " => (Key::Space.into(), false),
-            // TODO: cover more characters: -_=+[{]}\|;:'"`~
-            // TODO: we should error here
-            _ => (Key::Reserved.into(), false),
-        };
+impl TryFrom<u8> for ModifiedEvent {
+    type Error = anyhow::Error;
 
-        ModifiedEvent {
+    fn try_from(button: u8) -> Result<Self> {
+        let event = buttons::evdev_code(button)
+            .and_then(|code| {
+                MouseButton::iter_variants().find(|event| event.code() == c_int::from(code))
+            })
+            .ok_or_else(|| {
+                let mut valid = buttons::BUTTONS
+                    .iter()
+                    .map(|(_, button, _)| *button)
+                    .chain(4..=7)
+                    .collect::<Vec<_>>();
+                valid.sort_unstable();
+                Error::UnknownMouseButton(button, valid.into_iter().map(buttons::label).collect())
+            })?;
+        Ok(ModifiedEvent {
+            event: event.into(),
+            modifiers: Vec::new(),
+        })
+    }
+}
+
+/// The uinput event for a kernel key code, if the uinput crate knows it.
+fn keyboard_event(code: u16) -> Option<uinput::Event> {
+    Key::iter_variants()
+        .map(Keyboard::Key)
+        .chain(KeyPad::iter_variants().map(Keyboard::KeyPad))
+        .chain(Misc::iter_variants().map(Keyboard::Misc))
+        .chain(InputAssist::iter_variants().map(Keyboard::InputAssist))
+        .chain(Function::iter_variants().map(Keyboard::Function))
+        .chain(Braille::iter_variants().map(Keyboard::Braille))
+        .chain(Numeric::iter_variants().map(Keyboard::Numeric))
+        .chain(TouchPad::iter_variants().map(Keyboard::TouchPad))
+        .chain(Camera::iter_variants().map(Keyboard::Camera))
+        .chain(Attendant::iter_variants().map(Keyboard::Attendant))
+        .find(|event| event.code() == c_int::from(code))
+        .map(uinput::Event::Keyboard)
+}
+
+impl TryFrom<&Chord> for ModifiedEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(chord: &Chord) -> Result<Self> {
+        let event = keys::lookup(&chord.key)
+            .and_then(|key| keyboard_event(key.code))
+            .ok_or_else(|| Error::UnknownKey(chord.key.clone(), keys::suggest(&chord.key)))?;
+        Ok(ModifiedEvent {
             event,
-            shift,
-            alt: false,
-            control: false,
-        }
+            modifiers: chord
+                .held_modifiers()
+                .into_iter()
+                .map(modifier_key)
+                .collect(),
+        })
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct InputEvent {
-    pub event: ModifiedEvent,
-    pub interval: Duration,
-    pub remaining: Duration,
+impl ModifiedEvent {
+    /// The event for `key` on a layout, holding whatever reaches the key's
+    /// level on top of `modifiers`.
+    fn on_layout(modifiers: &[Modifier], key: LayoutKey) -> Option<Self> {
+        Some(ModifiedEvent {
+            event: keyboard_event(key.code)?,
+            modifiers: merge_modifiers(modifiers, &key.modifiers())
+                .into_iter()
+                .map(modifier_key)
+                .collect(),
+        })
+    }
 }
 
-impl std::fmt::Display for InputEvent {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{} every {:?}", self.event, self.interval)?;
-        if self.remaining > Duration::from_millis(0) {
-            write!(f, " ({:?} remaining)", self.remaining)?;
+/// The character a chord's key types, if it's a character or the keysym of
+/// one, e.g. `@`, `z` or `adiaeresis`.
+fn key_char(key: &str) -> Option<char> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => layout::keysym_char(key),
+    }
+}
+
+/// A scroll wheel, as a virtual device of its own, created once something is
+/// going to scroll.
+///
+/// The uinput crate can't register the high-resolution wheel axes, so the
+/// wheel is created through evdev instead. Each scroll is sent as
+/// high-resolution units for clients that scroll smoothly, along with a
+/// whole detent on the classic axis each time the units add up to one, for
+/// clients that don't.
+struct Wheel {
+    device: evdev::uinput::VirtualDevice,
+    /// High-resolution units sent since the last whole detent, per axis.
+    vertical: i32,
+    horizontal: i32,
+}
+
+impl Wheel {
+    fn new() -> Result<Self> {
+        let mut axes = evdev::AttributeSet::<evdev::RelativeAxisType>::new();
+        for axis in [
+            // A wheel with no pointer axes or buttons isn't taken for a mouse
+            evdev::RelativeAxisType::REL_X,
+            evdev::RelativeAxisType::REL_Y,
+            evdev::RelativeAxisType::REL_WHEEL,
+            evdev::RelativeAxisType::REL_HWHEEL,
+            evdev::RelativeAxisType::REL_WHEEL_HI_RES,
+            evdev::RelativeAxisType::REL_HWHEEL_HI_RES,
+        ] {
+            axes.insert(axis);
+        }
+        let mut buttons = evdev::AttributeSet::<evdev::Key>::new();
+        buttons.insert(evdev::Key::BTN_LEFT);
+        let device = evdev::uinput::VirtualDeviceBuilder::new()?
+            .name("clickrs wheel")
+            .with_relative_axes(&axes)?
+            .with_keys(&buttons)?
+            .build()?;
+        Ok(Wheel {
+            device,
+            vertical: 0,
+            horizontal: 0,
+        })
+    }
+
+    fn scroll(&mut self, direction: ScrollDirection, amount: u32) -> Result<()> {
+        let amount = i32::try_from(amount).unwrap_or(i32::MAX);
+        // Positive values scroll up and right
+        let (axis, hi_res_axis, sent, value) = match direction {
+            ScrollDirection::Up => (
+                evdev::RelativeAxisType::REL_WHEEL,
+                evdev::RelativeAxisType::REL_WHEEL_HI_RES,
+                &mut self.vertical,
+                amount,
+            ),
+            ScrollDirection::Down => (
+                evdev::RelativeAxisType::REL_WHEEL,
+                evdev::RelativeAxisType::REL_WHEEL_HI_RES,
+                &mut self.vertical,
+                -amount,
+            ),
+            ScrollDirection::Right => (
+                evdev::RelativeAxisType::REL_HWHEEL,
+                evdev::RelativeAxisType::REL_HWHEEL_HI_RES,
+                &mut self.horizontal,
+                amount,
+            ),
+            ScrollDirection::Left => (
+                evdev::RelativeAxisType::REL_HWHEEL,
+                evdev::RelativeAxisType::REL_HWHEEL_HI_RES,
+                &mut self.horizontal,
+                -amount,
+            ),
+        };
+        *sent = sent.saturating_add(value);
+        let detents = *sent / WHEEL_DETENT as i32;
+        *sent -= detents * WHEEL_DETENT as i32;
+
+        let mut events = vec![evdev::InputEvent::new(
+            evdev::EventType::RELATIVE,
+            hi_res_axis.0,
+            value,
+        )];
+        if detents != 0 {
+            events.push(evdev::InputEvent::new(
+                evdev::EventType::RELATIVE,
+                axis.0,
+                detents,
+            ));
         }
+        self.device.emit(&events)?;
         Ok(())
     }
 }
 
-impl From<EventSpec> for InputEvent {
-    fn from(eventspec: EventSpec) -> Self {
-        let remaining = Duration::from_millis(0);
-        match eventspec {
-            EventSpec::MouseEvent(button, interval) => InputEvent {
-                event: ModifiedEvent::from(button),
-                interval,
-                remaining,
-            },
-            EventSpec::KeyboardEvent(key, interval) => InputEvent {
-                event: ModifiedEvent::from(key.as_str()),
-                interval,
-                remaining,
-            },
-        }
+/// An absolute pointer, as another virtual device, for moves to a screen
+/// position.
+///
+/// Its axes span the screen in pixels, so a position is sent as it is, and
+/// pointer acceleration, which only applies to relative motion, can't throw
+/// it off.
+struct Tablet {
+    device: evdev::uinput::VirtualDevice,
+    size: ScreenSize,
+}
+
+impl Tablet {
+    fn new(size: ScreenSize) -> Result<Self> {
+        let axis = |axis, extent: u32| {
+            let maximum = i32::try_from(extent).unwrap_or(i32::MAX) - 1;
+            evdev::UinputAbsSetup::new(axis, evdev::AbsInfo::new(0, 0, maximum, 0, 0, 0))
+        };
+        // Absolute axes with a mouse button are taken for a mouse, not a
+        // touchscreen or a tablet with a stylus
+        let mut buttons = evdev::AttributeSet::<evdev::Key>::new();
+        buttons.insert(evdev::Key::BTN_LEFT);
+        let device = evdev::uinput::VirtualDeviceBuilder::new()?
+            .name("clickrs pointer")
+            .with_absolute_axis(&axis(evdev::AbsoluteAxisType::ABS_X, size.width))?
+            .with_absolute_axis(&axis(evdev::AbsoluteAxisType::ABS_Y, size.height))?
+            .with_keys(&buttons)?
+            .build()?;
+        Ok(Tablet { device, size })
+    }
+
+    /// Move the pointer to `x`, `y`, kept on the screen, and return where it
+    /// ended up.
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(i32, i32)> {
+        let clamp = |v: i32, extent: u32| v.clamp(0, i32::try_from(extent).unwrap_or(i32::MAX) - 1);
+        let (x, y) = (clamp(x, self.size.width), clamp(y, self.size.height));
+        self.device.emit(&[
+            evdev::InputEvent::new(
+                evdev::EventType::ABSOLUTE,
+                evdev::AbsoluteAxisType::ABS_X.0,
+                x,
+            ),
+            evdev::InputEvent::new(
+                evdev::EventType::ABSOLUTE,
+                evdev::AbsoluteAxisType::ABS_Y.0,
+                y,
+            ),
+        ])?;
+        Ok((x, y))
     }
 }
 
-pub struct InputEventQueue {
+/// Injects input through a virtual `/dev/uinput` device, pausing while
+/// numlock is off on every physical keyboard.
+///
+/// Relative moves go through a relative pointer, and are subject to pointer
+/// acceleration. Moves to a screen position need the screen size, to set up
+/// an absolute pointer with [`UInputBackend::set_screen_size`]; the pointer
+/// position is known after one, until the next relative move. Scrolls go
+/// through a wheel that is only set up if the events scroll.
+pub struct UInputBackend {
     numlock_state: NumlockWatcher,
     uinput_device: uinput::Device,
-    events: VecDeque<InputEvent>,
-    last_active: Instant,
+    wheel: Option<Wheel>,
+    tablet: Option<Tablet>,
+    unicode_input: UnicodeInput,
+    layout: Option<Layout>,
+    position: Option<(i32, i32)>,
 }
 
-impl std::fmt::Debug for InputEventQueue {
+impl std::fmt::Debug for UInputBackend {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "Numlock: {:?}, events: {:?}, last_active: {:?}",
-            &self.numlock_state, &self.events, &self.last_active
-        )
+        write!(f, "Numlock: {:?}", &self.numlock_state)
     }
 }
 
-fn duration_as_f32(duration: Duration) -> f32 {
-    (duration.as_secs() as f32) + ((duration.subsec_nanos() as f32) / 1000000000.0)
-}
-
-impl InputEventQueue {
+impl UInputBackend {
+    /// Create the virtual input device.
     pub fn new() -> Result<Self> {
         // See https://github.com/meh/rust-uinput
-        let device = uinput::default()?
+        let mut builder = uinput::default()?
             .name("clickrs")?
-            .event(uinput::event::Keyboard::All)?
-            .event(Controller(Mouse(Left)))?
-            .event(Controller(Mouse(Right)))?
-            .event(Controller(Mouse(Middle)))?
-            // Relative mouse events require a mouse button to be enabled in order to work
+            .event(uinput::event::Keyboard::All)?;
+        // Relative mouse events require a mouse button to be enabled in order to work
+        for button in MouseButton::iter_variants() {
+            builder = builder.event(Controller(Mouse(button)))?;
+        }
+        let device = builder
             .event(Relative(Position(X)))?
             .event(Relative(Position(Y)))?
             .create()?;
 
-        Ok(InputEventQueue {
+        Ok(UInputBackend {
             numlock_state: NumlockWatcher::default(),
             uinput_device: device,
-            events: VecDeque::new(),
-            last_active: Instant::now(),
+            wheel: None,
+            tablet: None,
+            unicode_input: UnicodeInput::default(),
+            layout: None,
+            position: None,
         })
     }
 
-    fn find_insertion_point(&self, event: &mut InputEvent) -> usize {
-        event.remaining = event.interval;
-        debug!(
-            "Looking for insertion point for event with {}s left",
-            duration_as_f32(event.remaining)
-        );
-        for (i, v_event) in self.events.iter().enumerate() {
-            debug!(
-                "	{} <=> {}",
-                duration_as_f32(event.remaining),
-                duration_as_f32(v_event.remaining)
-            );
-            if event.remaining < v_event.remaining {
-                debug!("	Found insertion point!");
-                return i;
+    /// Set how to type characters that aren't on any key. The device only
+    /// has physical keys, so it can't type them at all by default.
+    pub fn set_unicode_input(&mut self, unicode_input: UnicodeInput) {
+        self.unicode_input = unicode_input;
+    }
+
+    /// Set the size of the screen, creating the absolute pointer that moves
+    /// to screen positions are sent through.
+    pub fn set_screen_size(&mut self, size: ScreenSize) -> Result<()> {
+        self.tablet = Some(Tablet::new(size)?);
+        Ok(())
+    }
+
+    /// Set the keyboard layout the system will read the device's keys with,
+    /// so characters are typed with the keys that have them on it. Without
+    /// one, keys are sent as they are on a US layout.
+    pub fn set_layout(&mut self, layout: Option<Layout>) {
+        self.layout = layout;
+    }
+
+    /// The scroll wheel, created the first time it's needed.
+    fn wheel(&mut self) -> Result<&mut Wheel> {
+        let wheel = match self.wheel.take() {
+            Some(wheel) => wheel,
+            None => Wheel::new()?,
+        };
+        Ok(self.wheel.insert(wheel))
+    }
+
+    /// The event for a chord, finding its key on the layout if there is one
+    /// and the key is a character.
+    fn chord_event(&self, key: &str) -> Result<ModifiedEvent> {
+        let chord = Chord::parse(key)?;
+        match (&self.layout, key_char(&chord.key)) {
+            (Some(layout), Some(c)) => {
+                let key = layout.lookup(c).ok_or_else(|| {
+                    Error::NotOnLayout(chord.key.clone(), layout.name().to_owned())
+                })?;
+                ModifiedEvent::on_layout(&chord.modifiers, key)
+                    .ok_or_else(|| Error::UnknownKey(chord.key.clone(), Vec::new()).into())
             }
-            event.remaining -= v_event.remaining;
-            debug!(
-                "	time remaining after event in queue: {}",
-                duration_as_f32(event.remaining)
-            );
+            _ => ModifiedEvent::try_from(&chord),
         }
-        debug!("	at end of queue!");
-        self.events.len()
-    }
-
-    pub fn add_event(&mut self, mut event: InputEvent) {
-        let insert_index = self.find_insertion_point(&mut event);
-        if let Some(ref mut next_event) = self.events.get_mut(insert_index) {
-            debug!(
-                "current time delta for next event: {}",
-                duration_as_f32(next_event.remaining)
-            );
-            debug!(
-                "decrementing time delta for next event by {}",
-                duration_as_f32(event.remaining)
-            );
-            next_event.remaining -= event.remaining;
-            debug!(
-                "new time delta for next event: {}",
-                duration_as_f32(next_event.remaining)
-            );
+    }
+
+    /// The event for a character on the layout, if there is one.
+    fn layout_event(&self, c: char) -> Option<ModifiedEvent> {
+        let key = self.layout.as_ref()?.lookup(c)?;
+        ModifiedEvent::on_layout(&[], key)
+    }
+
+    fn press(&mut self, event: &ModifiedEvent) -> Result<()> {
+        for modifier in &event.modifiers {
+            self.uinput_device.press(modifier)?;
         }
-        self.events.insert(insert_index, event);
+
+        self.uinput_device.synchronize()?;
+        self.uinput_device.send(event.event, 1)?;
+        self.uinput_device.synchronize()?;
+        Ok(())
     }
 
-    pub fn run_next(&mut self) -> Result<()> {
-        let event = match self.events.pop_front() {
-            None => {
-                // Sleep here in case run_next is being called in a tight loop
-                // this way we yield time to the OS
-                debug!("Nothing to do...");
-                std::thread::sleep(Duration::from_millis(100));
-                return Ok(());
-            }
-            Some(e) => e,
-        };
-        debug!(
-            "wall time passed since last check: {:?}",
-            self.last_active.elapsed()
-        );
-        debug!("event time remaining: {:?}", event.remaining);
-        if event.remaining > self.last_active.elapsed() {
-            // sleep for however much time is left until the next event is ready
-            // minus however much time has passed since the last event ran
-            std::thread::sleep(event.remaining - self.last_active.elapsed());
-            self.last_active = Instant::now();
-        } else {
-            // we're in catch-up time
-            // fast-forward the internal clock by however much time was remaining on this event
-            self.last_active += event.remaining;
+    fn release(&mut self, event: &ModifiedEvent) -> Result<()> {
+        self.uinput_device.send(event.event, 0)?;
+        self.uinput_device.synchronize()?;
+
+        for modifier in event.modifiers.iter().rev() {
+            self.uinput_device.release(modifier)?;
         }
-        self.do_event(&event)?;
-        self.add_event(event);
+        self.uinput_device.synchronize()?;
         Ok(())
     }
+}
+
+impl InputBackend for UInputBackend {
+    fn key_down(&mut self, key: &str) -> Result<()> {
+        let event = self.chord_event(key)?;
+        debug!("{} -> {}", key, event);
+        self.press(&event)
+    }
 
-    pub fn paused(&self) -> bool {
-        debug!("Querying numlock state");
-        !self.numlock_state.enabled()
+    fn key_up(&mut self, key: &str) -> Result<()> {
+        self.release(&self.chord_event(key)?)
     }
 
-    pub fn start(&mut self, start_delay: Duration) -> Result<()> {
-        std::thread::sleep(start_delay);
-        let pause_poll = Duration::from_millis(500);
-        let mut noise_ctl = std::num::Wrapping(0_u64);
-        loop {
-            while !self.paused() {
-                self.run_next()?;
-            }
-            if noise_ctl.0 % 10 == 0 {
-                info!("Paused...");
-            }
-            noise_ctl += std::num::Wrapping(1_u64);
-            std::thread::sleep(pause_poll);
-            self.last_active = Instant::now();
+    fn button_down(&mut self, button: u8) -> Result<()> {
+        // The wheel buttons scroll a detent when pressed, as they do in X11
+        if let Some(direction) = buttons::wheel_direction(button) {
+            return self.scroll(direction, WHEEL_DETENT);
         }
+        let event = ModifiedEvent::try_from(button)?;
+        debug!("button {} -> {}", button, event);
+        self.press(&event)
     }
 
-    fn do_event(&mut self, event: &InputEvent) -> Result<()> {
-        info!(
-            "{} (next in {:2.3}s)",
-            event.event,
-            duration_as_f32(event.interval)
-        );
-        if event.event.shift {
-            self.uinput_device.press(&Key::LeftShift)?;
-        }
-        if event.event.alt {
-            self.uinput_device.press(&Key::LeftAlt)?;
-        }
-        if event.event.control {
-            self.uinput_device.press(&Key::LeftControl)?;
+    fn button_up(&mut self, button: u8) -> Result<()> {
+        if buttons::wheel_direction(button).is_some() {
+            return Ok(());
         }
+        self.release(&ModifiedEvent::try_from(button)?)
+    }
 
+    fn move_by(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.uinput_device.send(Relative(Position(X)), dx)?;
+        self.uinput_device.send(Relative(Position(Y)), dy)?;
         self.uinput_device.synchronize()?;
-        self.uinput_device.send(event.event.event, 1)?;
-        self.uinput_device.synchronize()?;
-        self.uinput_device.send(event.event.event, 0)?;
-        self.uinput_device.synchronize()?;
+        // Pointer acceleration scales the move by an amount only the
+        // compositor knows
+        self.position = None;
+        Ok(())
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> Result<()> {
+        let tablet = self.tablet.as_mut().ok_or(Error::AbsoluteMoveUnavailable)?;
+        self.position = Some(tablet.move_to(x, y)?);
+        Ok(())
+    }
 
-        if event.event.control {
-            self.uinput_device.release(&Key::LeftControl)?;
+    fn check_move_to(&mut self) -> Result<()> {
+        match self.tablet {
+            Some(_) => Ok(()),
+            None => Err(Error::AbsoluteMoveUnavailable.into()),
         }
-        if event.event.alt {
-            self.uinput_device.release(&Key::LeftAlt)?;
+    }
+
+    fn scroll(&mut self, direction: ScrollDirection, amount: u32) -> Result<()> {
+        self.wheel()?.scroll(direction, amount)
+    }
+
+    fn check_scroll(&mut self) -> Result<()> {
+        self.wheel().map(|_| ())
+    }
+
+    fn pointer_position(&mut self) -> Option<(i32, i32)> {
+        self.position
+    }
+
+    fn paused(&self) -> bool {
+        debug!("Querying numlock state");
+        !self.numlock_state.enabled()
+    }
+
+    fn check_key(&mut self, key: &str) -> Result<()> {
+        self.chord_event(key).map(|_| ())
+    }
+
+    fn type_unicode(&mut self, c: char) -> Result<()> {
+        if let Some(event) = self.layout_event(c) {
+            debug!("{} -> {}", c, event);
+            self.press(&event)?;
+            return self.release(&event);
         }
-        if event.event.shift {
-            self.uinput_device.release(&Key::LeftShift)?;
+        let keys = self
+            .unicode_input
+            .keys(c)
+            .ok_or(Error::UnicodeUnavailable(c))?;
+        debug!("{} -> {} with {}", c, keys.join(" "), self.unicode_input);
+        for key in keys {
+            self.emit_key(&key)?;
         }
         Ok(())
     }
+
+    fn check_unicode(&mut self, c: char) -> Result<()> {
+        if self.layout_event(c).is_some() {
+            return Ok(());
+        }
+        self.unicode_input
+            .keys(c)
+            .map(|_| ())
+            .ok_or_else(|| Error::UnicodeUnavailable(c).into())
+    }
+
+    fn check_button(&mut self, button: u8) -> Result<()> {
+        if buttons::wheel_direction(button).is_some() {
+            return self.check_scroll();
+        }
+        ModifiedEvent::try_from(button).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_kernel_key_has_a_uinput_event() {
+        let missing: Vec<&str> = keys::EVDEV_KEYS
+            .iter()
+            .filter(|(_, code)| keyboard_event(*code).is_none())
+            .map(|(name, _)| *name)
+            .collect();
+        assert!(missing.is_empty(), "{:?}", missing);
+    }
 }
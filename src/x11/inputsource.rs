@@ -1,16 +1,33 @@
-use std::collections::{HashMap, VecDeque};
-use std::rc::Rc;
-use std::sync::Mutex;
-use std::time::Duration;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::os::raw::c_int;
 
-use log::{debug, info};
+use log::debug;
 use x11::{xlib, xtest};
 
-use crate::eventspec::EventSpec;
+use crate::backend::InputBackend;
+use crate::buttons;
+use crate::chord::{key_level, Chord, Modifier};
+use crate::errors::Error;
+use crate::eventspec::{ScrollDirection, WHEEL_DETENT};
+use crate::keys;
 use anyhow::Result;
 
+/// The keysym for a character: Latin-1 characters are their own keysyms,
+/// and anything else is in the Unicode keysym range.
+fn char_keysym(c: char) -> xlib::KeySym {
+    let code = c as u32;
+    if (0x20..=0x7e).contains(&code) || (0xa0..=0xff).contains(&code) {
+        xlib::KeySym::from(code)
+    } else {
+        xlib::KeySym::from(0x0100_0000 | code)
+    }
+}
+
 // X11/extensions/XKB.h:#define    XkbUseCoreKbd           0x0100
 const XKBUSECOREKBD: u32 = 0x0100;
+
 // X11/X.h:#define None 0L
 //const XNONE: std::os::raw::c_int = 0;
 
@@ -19,7 +36,13 @@ pub struct XContext {
     pub display_name: Option<String>,
     display: *mut xlib::Display,
     window: Option<xlib::Window>,
-    key_name_to_code: HashMap<String, u8>,
+    /// The keycode and shift level for each key name looked up so far.
+    key_name_to_code: HashMap<String, (u8, u8)>,
+    /// A keycode with nothing bound to it, borrowed for characters that
+    /// aren't in the keymap, once one has been looked for.
+    spare_keycode: OnceCell<Option<u8>>,
+    /// Whether the spare keycode has something bound to it now.
+    remapped: bool,
 }
 
 impl std::fmt::Display for XContext {
@@ -30,38 +53,183 @@ impl std::fmt::Display for XContext {
 }
 
 impl XContext {
-    pub fn new(display_name: Option<String>) -> Self {
-        let name_ptr = match display_name {
-            Some(ref name_str) => name_str.as_ptr(),
-            None => std::ptr::null(),
+    pub fn new(display_name: Option<String>) -> Result<Self> {
+        let c_name = match display_name {
+            Some(ref name_str) => Some(
+                std::ffi::CString::new(name_str.as_str())
+                    .map_err(|_| Error::XDisplayOpen(name_str.to_owned()))?,
+            ),
+            None => None,
         };
-        unsafe {
-            let display = xlib::XOpenDisplay(name_ptr as *const i8);
-            if display.is_null() {
-                panic!("Failed to open specified display '{:?}'", display_name);
-            }
-            XContext {
-                display_name,
-                display,
-                window: None,
-                key_name_to_code: HashMap::new(),
-            }
+        let name_ptr = c_name
+            .as_ref()
+            .map_or(std::ptr::null(), |name| name.as_ptr());
+        let display = unsafe { xlib::XOpenDisplay(name_ptr) };
+        if display.is_null() {
+            return Err(Error::XDisplayOpen(display_name.unwrap_or_default()).into());
         }
+        Ok(XContext {
+            display_name,
+            display,
+            window: None,
+            key_name_to_code: HashMap::new(),
+            spare_keycode: OnceCell::new(),
+            remapped: false,
+        })
     }
 
-    pub fn keycode_lookup(&mut self, key_name: &str) -> u8 {
+    /// The keycode for a key name in the current keymap, and the first
+    /// shift level of that key with the name's keysym on it.
+    pub fn key_lookup(&mut self, key_name: &str) -> (u8, u8) {
         let display = self.display;
-        let keycode = self
+        let key = self
             .key_name_to_code
             .entry(key_name.to_owned())
-            .or_insert_with(|| unsafe {
-                let c_key_name =
-                    std::ffi::CString::new(key_name).expect("Invalid string key representation");
-                let keysym = xlib::XStringToKeysym(c_key_name.as_ptr());
-                xlib::XKeysymToKeycode(display, keysym)
+            .or_insert_with(|| {
+                let (keysym, keysym_keycode) = unsafe {
+                    let c_key_name = std::ffi::CString::new(keys::keysym_name(key_name))
+                        .expect("Invalid string key representation");
+                    let keysym = xlib::XStringToKeysym(c_key_name.as_ptr());
+                    (keysym, xlib::XKeysymToKeycode(display, keysym))
+                };
+                if keysym_keycode != 0 {
+                    let level = key_level(keysym, |level| unsafe {
+                        xlib::XkbKeycodeToKeysym(display, keysym_keycode, 0, c_int::from(level))
+                    });
+                    return (keysym_keycode, level.unwrap_or(0));
+                }
+                // Not a keysym the server knows, e.g. a kernel key name. X
+                // keycodes from the evdev driver are the kernel code plus 8.
+                let keycode = keys::lookup(key_name)
+                    .and_then(|key| u8::try_from(key.code + 8).ok())
+                    .unwrap_or(0);
+                (keycode, 0)
             });
-        debug!("{} -> {}", key_name, *keycode);
-        *keycode
+        debug!("{} -> {:?}", key_name, *key);
+        *key
+    }
+
+    pub fn keycode_lookup(&mut self, key_name: &str) -> u8 {
+        self.key_lookup(key_name).0
+    }
+
+    /// The highest keycode with no keysyms bound to it, if there is one.
+    fn find_spare_keycode(&self) -> Option<u8> {
+        let (mut min, mut max): (c_int, c_int) = (0, 0);
+        let mut per_keycode: c_int = 0;
+        unsafe {
+            xlib::XDisplayKeycodes(self.display, &mut min, &mut max);
+            let count = max - min + 1;
+            let mapping =
+                xlib::XGetKeyboardMapping(self.display, min as u8, count, &mut per_keycode);
+            if mapping.is_null() || per_keycode <= 0 {
+                return None;
+            }
+            let keysyms =
+                std::slice::from_raw_parts(mapping, (count * per_keycode) as usize).to_vec();
+            xlib::XFree(mapping as *mut std::os::raw::c_void);
+            keysyms
+                .chunks(per_keycode as usize)
+                .rposition(|keysyms| keysyms.iter().all(|keysym| *keysym == 0))
+                .and_then(|index| u8::try_from(min as usize + index).ok())
+        }
+    }
+
+    fn spare_keycode(&self) -> Option<u8> {
+        *self.spare_keycode.get_or_init(|| {
+            let keycode = self.find_spare_keycode();
+            debug!("Spare keycode: {:?}", keycode);
+            keycode
+        })
+    }
+
+    /// Bind `keysym` to the spare keycode, or unbind it if `keysym` is 0.
+    fn remap_spare_keycode(&mut self, keycode: u8, keysym: xlib::KeySym) {
+        let mut keysyms = [keysym, keysym];
+        unsafe {
+            xlib::XChangeKeyboardMapping(
+                self.display,
+                c_int::from(keycode),
+                2,
+                keysyms.as_mut_ptr(),
+                1,
+            );
+            xlib::XSync(self.display, xlib::False);
+        }
+        self.remapped = keysym != 0;
+    }
+
+    /// Put the spare keycode back the way it was, if it was borrowed.
+    fn restore_spare_keycode(&mut self) {
+        if let (true, Some(&Some(keycode))) = (self.remapped, self.spare_keycode.get()) {
+            debug!("Restoring spare keycode {}", keycode);
+            self.remap_spare_keycode(keycode, 0);
+        }
+    }
+
+    /// The keycode for `c` in the current keymap, and the shift level it's
+    /// on, if the keymap has the modifiers that reach that level.
+    fn char_keycode(&mut self, c: char) -> Option<(u8, u8)> {
+        let keysym = char_keysym(c);
+        let keycode = unsafe { xlib::XKeysymToKeycode(self.display, keysym) };
+        if keycode == 0 || self.spare_keycode.get() == Some(&Some(keycode)) {
+            return None;
+        }
+        let level = key_level(keysym, |level| unsafe {
+            xlib::XkbKeycodeToKeysym(self.display, keycode, 0, c_int::from(level))
+        })?;
+        Modifier::for_level(level)
+            .iter()
+            .all(|modifier| self.keycode_lookup(modifier.keysym_name()) != 0)
+            .then_some((keycode, level))
+    }
+
+    /// Whether `c` can be typed, either from the keymap or by borrowing a
+    /// spare keycode.
+    pub fn can_type_unicode(&mut self, c: char) -> bool {
+        self.char_keycode(c).is_some() || self.spare_keycode().is_some()
+    }
+
+    /// Type `c` with the key the keymap already has for it, or else by
+    /// binding it to a spare keycode. Clients read the key with the keymap
+    /// as it is when they get to it, so the spare keycode stays bound until
+    /// another key is pressed or the backend is dropped, rather than being
+    /// unbound straight away.
+    pub fn type_unicode(&mut self, c: char) -> Result<()> {
+        let (keycode, level) = match self.char_keycode(c) {
+            Some(found) => {
+                self.restore_spare_keycode();
+                found
+            }
+            None => {
+                let keycode = self.spare_keycode().ok_or(Error::UnicodeUnavailable(c))?;
+                self.remap_spare_keycode(keycode, char_keysym(c));
+                (keycode, 0)
+            }
+        };
+        debug!("{} -> keycode {} on level {}", c, keycode, level);
+        let modifiers: Vec<u8> = Modifier::for_level(level)
+            .iter()
+            .map(|modifier| self.keycode_lookup(modifier.keysym_name()))
+            .collect();
+        let saved = self.flip_to_saved_window();
+        for modifier in &modifiers {
+            self.fake_key_event(*modifier, true);
+        }
+        self.fake_key_event(keycode, true);
+        self.fake_key_event(keycode, false);
+        for modifier in modifiers.iter().rev() {
+            self.fake_key_event(*modifier, false);
+        }
+        self.restore_original_window(saved);
+        self.flush_events();
+        Ok(())
+    }
+
+    /// Number of buttons on the core pointer.
+    pub fn button_count(&self) -> u8 {
+        let count = unsafe { xlib::XGetPointerMapping(self.display, std::ptr::null_mut(), 0) };
+        u8::try_from(count).unwrap_or(u8::MAX)
     }
 
     pub fn get_window(&self) -> (xlib::Window, i32) {
@@ -101,44 +269,89 @@ impl XContext {
         }
     }
 
-    pub fn fake_button_event(&self, button: u8) {
+    pub fn fake_button_event(&self, button: u8, pressed: bool) {
+        let is_press = if pressed { xlib::True } else { xlib::False };
         unsafe {
-            xtest::XTestFakeButtonEvent(self.display, button as u32, xlib::True, xlib::CurrentTime);
-            xtest::XTestFakeButtonEvent(
-                self.display,
-                button as u32,
-                xlib::False,
-                xlib::CurrentTime,
-            );
+            xtest::XTestFakeButtonEvent(self.display, button as u32, is_press, xlib::CurrentTime);
         }
     }
 
-    pub fn send_button_event_to_window(&self, button: u8) {
+    pub fn send_button_event_to_window(&self, button: u8, pressed: bool) {
         let saved = self.flip_to_saved_window();
-        self.fake_button_event(button);
+        self.fake_button_event(button, pressed);
         self.restore_original_window(saved);
         self.flush_events();
     }
 
-    pub fn fake_key_event(&self, keycode: u8) {
+    pub fn fake_relative_motion(&self, dx: i32, dy: i32) {
         unsafe {
-            xtest::XTestFakeKeyEvent(self.display, keycode as u32, xlib::True, xlib::CurrentTime);
-            xtest::XTestFakeKeyEvent(self.display, keycode as u32, xlib::False, xlib::CurrentTime);
+            // -1 is the screen the pointer is currently on
+            xtest::XTestFakeRelativeMotionEvent(self.display, -1, dx, dy, xlib::CurrentTime);
         }
+        self.flush_events();
     }
 
-    pub fn send_key_event_to_window(&mut self, keycode: u8) {
-        let saved = self.flip_to_saved_window();
-        self.fake_key_event(keycode);
-        self.restore_original_window(saved);
+    pub fn fake_motion(&self, x: i32, y: i32) {
+        unsafe {
+            // -1 is the screen the pointer is currently on
+            xtest::XTestFakeMotionEvent(self.display, -1, x, y, xlib::CurrentTime);
+        }
         self.flush_events();
     }
 
-    pub fn send_key_to_window(&mut self, key_name: &str) {
-        let keycode = self.keycode_lookup(key_name);
-        self.send_key_event_to_window(keycode);
+    pub fn pointer_position(&self) -> Option<(i32, i32)> {
+        let mut root: xlib::Window = 0;
+        let mut child: xlib::Window = 0;
+        let (mut root_x, mut root_y, mut win_x, mut win_y) = (0, 0, 0, 0);
+        let mut mask: std::os::raw::c_uint = 0;
+        let on_screen = unsafe {
+            xlib::XQueryPointer(
+                self.display,
+                xlib::XDefaultRootWindow(self.display),
+                &mut root,
+                &mut child,
+                &mut root_x,
+                &mut root_y,
+                &mut win_x,
+                &mut win_y,
+                &mut mask,
+            )
+        };
+        if on_screen == xlib::True {
+            Some((root_x, root_y))
+        } else {
+            None
+        }
+    }
+
+    pub fn fake_key_event(&self, keycode: u8, pressed: bool) {
+        let is_press = if pressed { xlib::True } else { xlib::False };
+        unsafe {
+            xtest::XTestFakeKeyEvent(self.display, keycode as u32, is_press, xlib::CurrentTime);
+        }
+    }
+
+    /// Press a chord's modifiers and then its key, or release them in the
+    /// reverse order. Shift or AltGr is added if the keymap has the key on a
+    /// level that needs it.
+    pub fn send_chord_to_window(&mut self, chord: &Chord, pressed: bool) {
+        if pressed {
+            self.restore_spare_keycode();
+        }
+        let (keycode, level) = self.key_lookup(&chord.key);
+        let mut keycodes: Vec<u8> = chord
+            .modifiers_for_level(level)
+            .iter()
+            .map(|modifier| self.keycode_lookup(modifier.keysym_name()))
+            .collect();
+        keycodes.push(keycode);
+        if !pressed {
+            keycodes.reverse();
+        }
         let saved = self.flip_to_saved_window();
-        self.fake_key_event(keycode);
+        for keycode in keycodes {
+            self.fake_key_event(keycode, pressed);
+        }
         self.restore_original_window(saved);
         self.flush_events();
     }
@@ -210,223 +423,153 @@ impl XContext {
     */
 }
 
-#[derive(Debug, Clone)]
-pub enum InputType {
-    Keyboard(String),
-    XKeyboard(u8),
-    Mouse(u8),
+/// Injects input into an X11 display through the XTest extension, pausing
+/// while numlock is off.
+///
+/// The core protocol only knows whole wheel detents, sent as clicks of
+/// buttons 4 to 7, so fractions of a detent are saved up until they add up
+/// to one.
+#[derive(Debug)]
+pub struct X11Backend {
+    xctx: XContext,
+    /// Fractions of a detent scrolled but not yet sent, per axis; positive
+    /// values are up and right.
+    vertical: i32,
+    horizontal: i32,
 }
 
-impl InputType {
-    fn as_x<F: FnMut(String) -> u8>(&mut self, mut translate_keycode: F) {
-        if let InputType::Keyboard(key_name) = self {
-            *self = InputType::XKeyboard(translate_keycode(key_name.to_owned()))
-        }
+impl X11Backend {
+    /// Connect to `display_name`, or the `DISPLAY` env var if `None`.
+    pub fn new(display_name: Option<String>) -> Result<Self> {
+        Ok(X11Backend {
+            xctx: XContext::new(display_name)?,
+            vertical: 0,
+            horizontal: 0,
+        })
     }
 }
 
-impl std::fmt::Display for InputType {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match *self {
-            InputType::Keyboard(ref key) => write!(f, "key {:>8}", key),
-            InputType::XKeyboard(ref key) => write!(f, "key {:>8}", key),
-            InputType::Mouse(ref but) => write!(f, "button {:>5}", but),
-        }
+impl Drop for X11Backend {
+    fn drop(&mut self) {
+        // Don't leave the last character typed bound to the spare keycode
+        self.xctx.restore_spare_keycode();
     }
 }
 
-fn duration_as_f32(duration: Duration) -> f32 {
-    (duration.as_secs() as f32) + ((duration.subsec_nanos() as f32) / 1000000000.0)
-}
-
-#[derive(Debug, Clone)]
-pub struct InputEvent {
-    pub event: InputType,
-    pub interval: Duration,
-    pub remaining: Duration,
-}
+impl InputBackend for X11Backend {
+    fn key_down(&mut self, key: &str) -> Result<()> {
+        self.xctx.send_chord_to_window(&Chord::parse(key)?, true);
+        Ok(())
+    }
 
-impl std::fmt::Display for InputEvent {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{} every {:?}", self.event, self.interval)?;
-        if self.remaining > Duration::from_millis(0) {
-            write!(f, " ({:?} remaining)", self.remaining)?;
-        }
+    fn key_up(&mut self, key: &str) -> Result<()> {
+        self.xctx.send_chord_to_window(&Chord::parse(key)?, false);
         Ok(())
     }
-}
 
-impl From<EventSpec> for InputEvent {
-    fn from(eventspec: EventSpec) -> Self {
-        let remaining = std::Duration::from_millis(0);
-        match eventspec {
-            EventSpec::MouseEvent(button, interval) => InputEvent {
-                event: InputType::Mouse(button),
-                interval,
-                remaining,
-            },
-            EventSpec::KeyboardEvent(key, interval) => InputEvent {
-                event: InputType::Keyboard(key),
-                interval,
-                remaining,
-            },
-        }
+    fn button_down(&mut self, button: u8) -> Result<()> {
+        self.xctx.send_button_event_to_window(button, true);
+        Ok(())
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct InputEventQueue {
-    events: VecDeque<InputEvent>,
-    xctx: Rc<Mutex<XContext>>,
-    last_active: time::Instant,
-}
+    fn button_up(&mut self, button: u8) -> Result<()> {
+        self.xctx.send_button_event_to_window(button, false);
+        Ok(())
+    }
 
-impl InputEventQueue {
-    pub fn new(xctx: Rc<Mutex<XContext>>) -> Self {
-        InputEventQueue {
-            events: VecDeque::new(),
-            xctx,
-            last_active: time::Instant::now(),
-        }
+    fn move_by(&mut self, dx: i32, dy: i32) -> Result<()> {
+        self.xctx.fake_relative_motion(dx, dy);
+        Ok(())
     }
 
-    fn find_insertion_point(&self, event: &mut InputEvent) -> usize {
-        event.remaining = event.interval;
-        debug!(
-            "Looking for insertion point for event with {}s left",
-            duration_as_f32(event.remaining)
-        );
-        for (i, v_event) in self.events.iter().enumerate() {
-            debug!(
-                "	{} <=> {}",
-                duration_as_f32(event.remaining),
-                duration_as_f32(v_event.remaining)
-            );
-            if event.remaining < v_event.remaining {
-                debug!("	Found insertion point!");
-                return i;
-            }
-            event.remaining -= v_event.remaining;
-            debug!(
-                "	time remaining after event in queue: {}",
-                duration_as_f32(event.remaining)
-            );
-        }
-        debug!("	at end of queue!");
-        self.events.len()
-    }
-
-    pub fn add_event(&mut self, mut event: InputEvent) {
-        let insert_index = self.find_insertion_point(&mut event);
-        // Convert keyboard key name to keycode before inserting
-        let mut xctx = self.xctx.lock().expect("X Context lock busy.");
-        event.event.as_x(|name| xctx.keycode_lookup(name.as_str()));
-        if let Some(ref mut next_event) = self.events.get_mut(insert_index) {
-            debug!(
-                "current time delta for next event: {}",
-                duration_as_f32(next_event.remaining)
-            );
-            debug!(
-                "decrementing time delta for next event by {}",
-                duration_as_f32(event.remaining)
-            );
-            next_event.remaining -= event.remaining;
-            debug!(
-                "new time delta for next event: {}",
-                duration_as_f32(next_event.remaining)
-            );
-        }
-        self.events.insert(insert_index, event);
+    fn move_to(&mut self, x: i32, y: i32) -> Result<()> {
+        self.xctx.fake_motion(x, y);
+        Ok(())
     }
 
-    pub fn run_next(&mut self) -> Result<()> {
-        let event = match self.events.pop_front() {
-            None => {
-                // Sleep here in case run_next is being called in a tight loop
-                // this way we yield time to the OS
-                debug!("Nothing to do...");
-                std::thread::sleep(Duration::from_millis(100));
-                return Ok(());
-            }
-            Some(e) => e,
+    fn scroll(&mut self, direction: ScrollDirection, amount: u32) -> Result<()> {
+        let amount = i32::try_from(amount).unwrap_or(i32::MAX);
+        let (scrolled, value) = match direction {
+            ScrollDirection::Up => (&mut self.vertical, amount),
+            ScrollDirection::Down => (&mut self.vertical, -amount),
+            ScrollDirection::Right => (&mut self.horizontal, amount),
+            ScrollDirection::Left => (&mut self.horizontal, -amount),
         };
-        debug!(
-            "wall time passed since last check: {:?}",
-            self.last_active.elapsed()
-        );
-        debug!("event time remaining: {:?}", event.remaining);
-        if event.remaining > self.last_active.elapsed() {
-            // sleep for however much time is left until the next event is ready
-            // minus however much time has passed since the last event ran
-            std::thread::sleep(event.remaining - self.last_active.elapsed());
-            self.last_active = time::Instant::now();
-        } else {
-            // we're in catch-up time
-            // fast-forward the internal clock by however much time was remaining on this event
-            self.last_active += event.remaining;
+        *scrolled = scrolled.saturating_add(value);
+        let detents = *scrolled / WHEEL_DETENT as i32;
+        *scrolled -= detents * WHEEL_DETENT as i32;
+
+        let button = match direction {
+            ScrollDirection::Up | ScrollDirection::Down if detents > 0 => 4,
+            ScrollDirection::Up | ScrollDirection::Down => 5,
+            _ if detents > 0 => 7,
+            _ => 6,
+        };
+        for _ in 0..detents.unsigned_abs() {
+            self.xctx.send_button_event_to_window(button, true);
+            self.xctx.send_button_event_to_window(button, false);
         }
-        //self.do_event(&event)?;
-        self.do_event_fake(&event)?;
-        self.add_event(event);
         Ok(())
     }
 
-    pub fn paused(&self) -> bool {
+    fn pointer_position(&mut self) -> Option<(i32, i32)> {
+        self.xctx.pointer_position()
+    }
+
+    fn paused(&self) -> bool {
         debug!("Querying numlock state");
         let mut indicators: u32 = 0;
-        let xctx = self.xctx.lock().expect("X Context lock busy.");
         unsafe {
-            xlib::XkbGetIndicatorState(xctx.display, XKBUSECOREKBD, &mut indicators as *mut u32);
+            xlib::XkbGetIndicatorState(
+                self.xctx.display,
+                XKBUSECOREKBD,
+                &mut indicators as *mut u32,
+            );
         }
         // Checking numlock state
         (indicators & 0x02) != 0x02
     }
 
-    pub fn start(&mut self, start_delay: std::Duration) -> Result<()> {
-        std::thread::sleep(start_delay);
-        let pause_poll = Duration::from_millis(500);
-        let mut noise_ctl = std::num::Wrapping(0_u64);
-        loop {
-            while !self.paused() {
-                self.run_next()?;
-            }
-            if noise_ctl.0 % 10 == 0 {
-                info!("Paused...");
+    fn check_key(&mut self, key: &str) -> Result<()> {
+        let chord = Chord::parse(key)?;
+        let (keycode, level) = self.xctx.key_lookup(&chord.key);
+        let unknown = if keycode == 0 {
+            Some(chord.key.clone())
+        } else {
+            chord
+                .modifiers_for_level(level)
+                .iter()
+                .map(|modifier| modifier.keysym_name().to_owned())
+                .find(|name| self.xctx.keycode_lookup(name) == 0)
+        };
+        match unknown {
+            Some(name) => {
+                let suggestions = keys::suggest(&name);
+                Err(Error::UnknownKey(name, suggestions).into())
             }
-            noise_ctl += std::num::Wrapping(1_u64);
-            std::thread::sleep(pause_poll);
-            self.last_active = time::Instant::now();
+            None => Ok(()),
         }
     }
 
-    fn do_event_fake(&self, event: &InputEvent) -> Result<()> {
-        info!(
-            "{} (next in {:2.3}s)",
-            event.event,
-            duration_as_f32(event.interval)
-        );
-        let mut xctx = self.xctx.lock().expect("X Context lock busy.");
-        match event.event {
-            InputType::Mouse(ref button) => xctx.send_button_event_to_window(*button),
-            InputType::Keyboard(ref key) => xctx.send_key_to_window(key),
-            InputType::XKeyboard(ref key) => xctx.send_key_event_to_window(*key),
+    fn type_unicode(&mut self, c: char) -> Result<()> {
+        self.xctx.type_unicode(c)
+    }
+
+    fn check_unicode(&mut self, c: char) -> Result<()> {
+        if self.xctx.can_type_unicode(c) {
+            Ok(())
+        } else {
+            Err(Error::UnicodeUnavailable(c).into())
         }
-        Ok(())
     }
 
-    /*
-    fn do_event(&self, event: &InputEvent) -> Result<()> {
-        info!(
-            "{} (next in {:2.3}s)",
-            event.event,
-            duration_as_f32(event.interval)
-        );
-        let mut xctx = self.xctx.lock().expect("X Context lock busy.");
-        match event.event {
-            InputType::Mouse(ref button) => xctx.mouse_xevent(*button),
-            InputType::Keyboard(ref key) => xctx.key_xevent(key),
+    fn check_button(&mut self, button: u8) -> Result<()> {
+        let count = self.xctx.button_count();
+        if (1..=count).contains(&button) {
+            Ok(())
+        } else {
+            let valid = (1..=count).map(buttons::label).collect();
+            Err(Error::UnknownMouseButton(button, valid).into())
         }
-        Ok(())
     }
-    */
 }